@@ -0,0 +1,15 @@
+#version 450
+
+// compiled to default.frag.spv with `glslc default.frag -o default.frag.spv`.
+
+// set 1 is the material of the batch.
+layout(set = 1, binding = 0) uniform sampler2D texSampler;
+
+layout(location = 0) in vec3 fragColor;
+layout(location = 1) in vec2 fragTexCoord;
+
+layout(location = 0) out vec4 outColor;
+
+void main() {
+    outColor = texture(texSampler, fragTexCoord);
+}
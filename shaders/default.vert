@@ -0,0 +1,28 @@
+#version 450
+
+// compiled to default.vert.spv with `glslc default.vert -o default.vert.spv`.
+
+// set 0 is written once per frame, the camera slot is picked with a dynamic offset.
+layout(set = 0, binding = 0) uniform Camera {
+    mat4 view;
+    mat4 proj;
+} camera;
+
+// model of every instance drawn in the frame, each batch starts at its first instance.
+layout(std430, set = 0, binding = 1) readonly buffer Instances {
+    mat4 models[];
+};
+
+layout(location = 0) in vec3 inPosition;
+layout(location = 1) in vec3 inColor;
+layout(location = 2) in vec2 inTexCoord;
+
+layout(location = 0) out vec3 fragColor;
+layout(location = 1) out vec2 fragTexCoord;
+
+void main() {
+    gl_Position = camera.proj * camera.view * models[gl_InstanceIndex] * vec4(inPosition, 1.0);
+
+    fragColor = inColor;
+    fragTexCoord = inTexCoord;
+}
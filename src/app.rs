@@ -1,93 +1,448 @@
-use cgmath::{Vector2, Vector3};
+use std::{cell::Cell, env, io, iter, path::{Path, PathBuf}, rc::Rc};
 
-use crate::{core::{camera::Camera, input::InputManager, surface::Win32Window, time::Fps}, renderer::Renderer};
+use cgmath::{Vector2, Vector3, Zero};
+
+use crate::{core::{action::ActionMap, camera::{Camera, CameraController, FlyController, FollowController, OrbitController, Viewport}, ecs::Schedule, entity::{Entity, EntityJoin}, event::Event, input::InputManager, replay::{self, InputPlayback, InputRecording}, scene::{Scene, SceneCamera}, time::{FixedTimestep, Scheduler, Time}, window::{CursorMode, PlatformWindow, Window}}, renderer::Renderer};
 
 pub const NAME: &str = "Rail";
 
+/// Bindings file read at startup, the defaults below are used when it is missing.
+pub const INPUT_CONFIG: &str = "input.cfg";
+
+const DEFAULT_BINDINGS: &str = "\
+axis move_forward = key W 1, key S -1, gamepad_axis LeftStickY 1
+axis move_right = key D 1, key A -1, gamepad_axis LeftStickX 1
+axis move_up = key J 1, key K -1, gamepad RightShoulder 1, gamepad LeftShoulder -1
+axis look_x = mouse_axis X 1, gamepad_axis RightStickX 1
+axis look_y = mouse_axis Y 1, gamepad_axis RightStickY -1
+axis zoom = mouse_axis Wheel 1, gamepad_axis RightTrigger 0.2, gamepad_axis LeftTrigger -0.2
+action orbit = mouse Right, gamepad LeftStick
+action camera_fly = key F1
+action camera_orbit = key F2
+action camera_follow = key F3
+action picture_in_picture = key F4
+action pick = mouse Left
+action save_scene = key F5
+action reload_assets = key F6
+";
+
+/// Scene the `save_scene` action writes to when the app was not started from a scene file.
+pub const SCENE_FILE: &str = "rail.scene";
+
+const DEFAULT_SCENE: &str = "\
+mesh rail = res/Rail.obj
+# the viking is picked by its triangles, the rail only by its bounds.
+mesh viking = res/Viking.obj, geometry
+texture rail = res/Rail.png
+texture viking = res/Viking.png
+camera main = position 0 3 6, look_at -0.5 1 1
+entity rail = position 1 1 1, rotation 1 1 1, mesh rail, texture rail
+entity viking = position -2 1 1, rotation 1 1 1, mesh viking, texture viking
+entity sun = rotation -0.8 0.5 0, light directional 1 1 1 1
+";
+
+/// Point the orbit and overview cameras turn around, the rail scene starts looking at it.
+const FOCUS: Vector3<f32> = Vector3 { x: -0.5, y: 1.0, z: 1.0 };
+
+/// Entity the follow camera tracks.
+const FOLLOWED: &str = "rail";
+
+/// Seconds between checks for edited prefab files.
+const PREFAB_CHECK_INTERVAL: f32 = 1.0;
+
+/// Simulation steps per second of `App::fixed_update`.
+pub const TICK_RATE: f32 = 60.0;
+
 pub struct App {
-    camera: Camera,
-    pub(crate) input: InputManager
+    /// Cameras of the scene, the controller moves the first one.
+    cameras: Vec<SceneCamera>,
+    controller: Box<dyn CameraController>,
+    /// How far the last fixed step moved the main camera. The drawn camera trails it by the part
+    /// of a step not simulated yet.
+    last_step: Vector3<f32>,
+    /// Main camera as drawn this frame, between the last two fixed steps.
+    render_camera: Camera,
+    /// Top down view drawn in a corner over the main camera.
+    overview: Option<Camera>,
+    timestep: FixedTimestep,
+    pub(crate) time: Time,
+    pub(crate) scheduler: Scheduler,
+    /// Game logic over the entities, run every fixed step.
+    pub(crate) systems: Schedule,
+
+    pub(crate) input: InputManager,
+    pub(crate) actions: ActionMap,
+    /// Where the `save_scene` action writes the scene.
+    scene_path: PathBuf,
+    /// Set by the scheduler when the prefab files should be checked for edits.
+    is_prefab_check_due: Rc<Cell<bool>>,
 }
 
 impl App {
-    fn new() -> Self {
-        let camera = Camera::new(Vector2::new(1080, 720));
+    fn new(scene: &Scene, scene_path: &Path) -> Self {
+        let mut cameras = scene.cameras.clone();
+        if cameras.is_empty() {
+            // a scene without cameras is seen from the origin.
+            cameras.push(SceneCamera { name: "main".to_owned(), camera: Camera::new() });
+        }
+        let controller = Box::new(OrbitController::new(&cameras[0].camera, FOCUS));
         let input = InputManager::new();
 
+        let actions = match ActionMap::load(Path::new(INPUT_CONFIG)) {
+            Ok(actions) => actions,
+            Err(error) => {
+                if error.kind() != std::io::ErrorKind::NotFound {
+                    println!("Ignoring {INPUT_CONFIG}: {error}");
+                }
+                ActionMap::parse(DEFAULT_BINDINGS).expect("Failed to parse the default bindings!")
+            }
+        };
+
+        let mut scheduler = Scheduler::new();
+        let is_prefab_check_due = Rc::new(Cell::new(false));
+        let is_due = is_prefab_check_due.clone();
+        scheduler.every(PREFAB_CHECK_INTERVAL, move || is_due.set(true));
+
         Self {
-            camera,
-            input
+            last_step: Vector3::zero(),
+            render_camera: cameras[0].camera.clone(),
+            cameras,
+            controller,
+            overview: None,
+            timestep: FixedTimestep::new(TICK_RATE),
+            time: Time::new(),
+            scheduler,
+            systems: Schedule::new(),
+            input,
+            actions,
+            scene_path: scene_path.to_owned(),
+            is_prefab_check_due,
+        }
+    }
+    
+    /// Apply one frame of input, then run the fixed steps it is due and the frame update.
+    /// Camera controllers track the entity named `FOLLOWED`.
+    fn frame(&mut self, events: impl IntoIterator<Item = Event>, delta_time: f32, entities: &mut EntityJoin) {
+        self.input.begin_frame();
+        for event in events {
+            self.input.handle_event(event);
+        }
+
+        if let Some(transform) = entities.find(FOLLOWED).and_then(|id| entities.world_transform(id)) {
+            self.controller.attach(transform.w.truncate());
+        }
+
+        self.scheduler.advance(delta_time);
+
+        for _ in 0..self.timestep.advance(delta_time) {
+            self.fixed_update(entities, self.timestep.step());
+        }
+
+        self.update(entities, delta_time, self.timestep.alpha());
+    }
+
+    /// Simulation at a constant `TICK_RATE`, independent of the frame rate. Moves the main camera
+    /// and runs the systems in order.
+    fn fixed_update(&mut self, entities: &mut EntityJoin, step: f32) {
+        let camera = &mut self.cameras[0].camera;
+        let position = camera.position;
+        self.controller.step(camera, &self.input, &self.actions, step);
+        self.last_step = camera.position - position;
+
+        self.systems.run(entities, step);
+    }
+
+    /// Once per rendered frame, `alpha` is how far the frame is between the last two fixed steps.
+    /// The camera is turned here since mouse look is measured per frame.
+    fn update(&mut self, _entities: &mut EntityJoin, _delta_time: f32, alpha: f32) {
+        if self.actions.just_pressed(&self.input, "camera_fly") {
+            self.set_camera_controller(Box::<FlyController>::default());
+        } else if self.actions.just_pressed(&self.input, "camera_orbit") {
+            self.set_camera_controller(Box::new(OrbitController::new(&self.cameras[0].camera, FOCUS)));
+        } else if self.actions.just_pressed(&self.input, "camera_follow") {
+            self.set_camera_controller(Box::new(FollowController::new(Vector3 { x: 0.0, y: 2.0, z: 5.0 })));
+        }
+
+        if self.actions.just_pressed(&self.input, "picture_in_picture") {
+            self.overview = match self.overview {
+                Some(_) => None,
+                None => Some(Self::overview_camera()),
+            };
         }
+
+        self.controller.look(&mut self.cameras[0].camera, &self.input, &self.actions);
+
+        self.render_camera = self.cameras[0].camera.clone();
+        self.render_camera.position -= self.last_step * (1.0 - alpha);
+    }
+
+    fn overview_camera() -> Camera {
+        let mut overview = Camera::new();
+        overview.set_viewport(Viewport { x: 0.7, y: 0.05, width: 0.25, height: 0.25 });
+        overview.set_position(FOCUS + Vector3 { x: 0.0, y: 12.0, z: 0.0 });
+        // looking straight down, so world up can not be the camera up.
+        overview.look_at(FOCUS, -Vector3::unit_z());
+        overview
+    }
+
+    /// Cameras drawn this frame, the main one first.
+    fn cameras(&self) -> Vec<&Camera> {
+        let scene_cameras = self.cameras.iter().skip(1).map(|camera| &camera.camera);
+
+        iter::once(&self.render_camera).chain(scene_cameras).chain(self.overview.as_ref()).collect()
+    }
+
+    pub(crate) fn set_camera_controller(&mut self, controller: Box<dyn CameraController>) {
+        self.controller = controller;
+        // the new controller did not make the last step.
+        self.last_step = Vector3::zero();
     }
     
-    fn run(mut self, renderer: &mut Renderer, window: Win32Window) {
-        let mut tick_counter = Fps::new();
+    /// Report the entity under the cursor, picking through the topmost camera there.
+    fn pick(&self, renderer: &Renderer) {
+        let cursor = self.input.cursor_position();
+        let extent = renderer.extent();
+        let Some(camera) = self.cameras().into_iter().rev().find(|camera| camera.viewport().contains(cursor, extent)) else {
+            return;
+        };
+
+        match renderer.pick(camera, cursor) {
+            Some(pick) => println!(
+                "Picked {} at {:?}, barycentrics {:?}",
+                renderer.entities().get(pick.entity).and_then(Entity::name).unwrap_or("an unnamed entity"),
+                pick.point, pick.barycentrics
+            ),
+            None => println!("Picked nothing"),
+        }
+    }
+
+    /// Write the entities as they are now and the scene cameras to `scene_path`.
+    fn save_scene(&self, renderer: &Renderer) {
+        match renderer.scene(self.cameras.clone()).save(&self.scene_path) {
+            Ok(()) => println!("Saved the scene to {}", self.scene_path.display()),
+            Err(error) => println!("Failed to save the scene to {}: {error}", self.scene_path.display()),
+        }
+    }
+
+    /// Load the meshes and textures from their files again.
+    fn reload_assets(&self, renderer: &mut Renderer) {
+        let errors = renderer.reload_assets();
+        for error in &errors {
+            println!("Failed to reload an asset: {error}");
+        }
 
-        renderer.record();
+        if errors.is_empty() {
+            println!("Reloaded the meshes and textures");
+        }
+    }
 
-        let speed = 3.0;
+    /// Update the instances of every prefab whose file was edited.
+    fn reload_prefabs(&self, renderer: &mut Renderer) {
+        let (reloaded, errors) = renderer.reload_prefabs();
+        for name in reloaded {
+            println!("Reloaded prefab {name}");
+        }
+        for error in errors {
+            println!("Failed to reload a prefab: {error}");
+        }
+    }
 
-        loop {
-            if window.update(&mut self) == false {
-                renderer.device.wait_idle();
-                break;
+    fn run(mut self, renderer: &mut Renderer, window: &mut dyn Window, source: &mut InputSource) {
+        let mut is_minimized = false;
+        let mut cursor_mode = CursorMode::Normal;
+
+        'running: loop {
+            let mut events = window.poll_events();
+
+            let delta_time = match source {
+                InputSource::Live => self.time.delta_time(),
+                InputSource::Record(recording) => {
+                    recording.push_frame(self.time.delta_time(), &events);
+                    self.time.delta_time()
+                }
+                InputSource::Replay(playback) => {
+                    let Some(frame) = playback.next_frame() else {
+                        renderer.device.wait_idle();
+                        break 'running;
+                    };
+
+                    // the input comes from the recording, only the window events are live.
+                    events.retain(|event| !replay::is_input(event));
+                    events.extend(frame.events.iter().copied());
+                    frame.delta_time
+                }
+            };
+
+            for event in &events {
+                match event {
+                    Event::CloseRequested => {
+                        renderer.device.wait_idle();
+                        break 'running;
+                    }
+                    Event::Resized(_) => renderer.resize_framebuffer(),
+                    Event::Minimized(minimized) => is_minimized = *minimized,
+                    _ => ()
+                }
             }
-            
-            match self.input.input {
-                //-z
-                /*W*/87 => {
-                    self.camera.position += Vector3 { 
-                        x: 0.0, y: 0.0, z: -1.0 
-                    } * speed * tick_counter.delta_time();
-                },
-                //+z
-                /*S*/83 => {
-                    self.camera.position += Vector3 { 
-                        x: 0.0, y: 0.0, z: 1.0 
-                    } * speed * tick_counter.delta_time();
-                },
-                //-x
-                /*A*/65 => {
-                    self.camera.position += Vector3 { 
-                        x: 1.0, y: 0.0, z: 0.0 
-                    } * speed * tick_counter.delta_time();
-                },
-                //+x
-                    /*D*/68 => {
-                    self.camera.position += Vector3 { 
-                        x: -1.0, y: 0.0, z: 0.0 
-                    } * speed * tick_counter.delta_time();
-                },
-                //+y
-                    /*J*/74 => {
-                    self.camera.position += Vector3 { 
-                        x: 0.0, y: 1.0, z: 0.0 
-                    } * speed * tick_counter.delta_time();
-                },
-                //-y
-                    /*K*/75 => {
-                    self.camera.position += Vector3 { 
-                        x: 0.0, y: -1.0, z: 0.0 
-                    } * speed * tick_counter.delta_time();
-                },
-                _ => ()
+
+            self.frame(events, delta_time, renderer.entities_mut());
+
+            if self.actions.just_pressed(&self.input, "save_scene") {
+                self.save_scene(renderer);
             }
 
-            renderer.draw(&window, &self.camera);
+            if self.actions.just_pressed(&self.input, "reload_assets") {
+                self.reload_assets(renderer);
+            }
 
-            tick_counter.tick_frame();
+            if self.is_prefab_check_due.replace(false) {
+                self.reload_prefabs(renderer);
+            }
+
+            if self.controller.cursor_mode() != cursor_mode {
+                cursor_mode = self.controller.cursor_mode();
+                window.set_cursor_mode(cursor_mode);
+            }
+
+            // a minimized window has no area to present to.
+            if !is_minimized {
+                renderer.draw(&*window, &self.cameras());
+
+                if self.actions.just_pressed(&self.input, "pick") {
+                    self.pick(renderer);
+                }
+            }
+
+            self.time.tick();
+        }
+    }
+}
+
+/// Where the input of each frame comes from.
+enum InputSource {
+    Live,
+    Record(InputRecording),
+    Replay(InputPlayback),
+}
+
+fn rail_scene() -> Scene {
+    Scene::parse(DEFAULT_SCENE).expect("Failed to parse the default scene!")
+}
+
+/// Run the scene file given as the first command line argument, or the rail scene without one.
+pub fn run() -> io::Result<()> {
+    match env::args_os().nth(1) {
+        Some(path) => run_scene(Path::new(&path)),
+        None => {
+            run_rail();
+            Ok(())
         }
     }
 }
 
+/// Run the scene stored at `path`, the `save_scene` action writes it back there.
+pub fn run_scene(path: &Path) -> io::Result<()> {
+    let scene = Scene::load(path)?;
+
+    let mut window = PlatformWindow::new();
+
+    run_in(&mut window, &scene, path);
+
+    Ok(())
+}
+
 pub fn run_rail() {
-    let app = App::new();
+    let mut window = PlatformWindow::new();
+
+    run_rail_in(&mut window);
+}
+
+/// Run the rail scene inside a window created by the caller, e.g. a Wayland surface.
+pub fn run_rail_in(window: &mut dyn Window) {
+    run_in(window, &rail_scene(), Path::new(SCENE_FILE));
+}
+
+fn run_in(window: &mut dyn Window, scene: &Scene, scene_path: &Path) {
+    let app = App::new(scene, scene_path);
+
+    let mut renderer = Renderer::new(&*window, scene);
+
+    app.run(&mut renderer, window, &mut InputSource::Live);
+
+    renderer.destroy();
+}
 
-    let window = Win32Window::new();
+/// Run the rail scene and save its input stream to `output` once the window is closed.
+pub fn record_rail(output: &Path) -> io::Result<()> {
+    let mut window = PlatformWindow::new();
+    let scene = rail_scene();
+    let app = App::new(&scene, Path::new(SCENE_FILE));
 
-    let mut renderer = Renderer::new(&window);
+    let mut renderer = Renderer::new(&window, &scene);
 
-    app.run(&mut renderer, window);
+    let mut source = InputSource::Record(InputRecording::new());
+    app.run(&mut renderer, &mut window, &mut source);
 
     renderer.destroy();
+
+    match source {
+        InputSource::Record(recording) => recording.save(output),
+        _ => unreachable!(),
+    }
+}
+
+/// Run the rail scene driven by a recorded input stream, stopping when it ends.
+pub fn replay_rail(recording: &Path) -> io::Result<()> {
+    let recording = InputRecording::load(recording)?;
+
+    let mut window = PlatformWindow::new();
+    let scene = rail_scene();
+    let app = App::new(&scene, Path::new(SCENE_FILE));
+
+    let mut renderer = Renderer::new(&window, &scene);
+
+    app.run(&mut renderer, &mut window, &mut InputSource::Replay(InputPlayback::new(recording)));
+
+    renderer.destroy();
+
+    Ok(())
+}
+
+/// Render a single frame of the rail scene without a window and save it as PNG.
+pub fn capture_rail(size: Vector2<u32>, output: &Path) -> image::ImageResult<()> {
+    let scene = rail_scene();
+    let app = App::new(&scene, Path::new(SCENE_FILE));
+
+    let mut renderer = Renderer::headless(size, &scene);
+
+    renderer.draw_offscreen(&app.cameras());
+
+    let result = renderer.save_frame(output);
+
+    renderer.destroy();
+
+    result
+}
+
+/// Replay a recorded input stream without a window and save the last frame as PNG,
+/// so a recorded session can be checked against a reference image.
+pub fn replay_rail_headless(recording: &Path, size: Vector2<u32>, output: &Path) -> image::ImageResult<()> {
+    let recording = InputRecording::load(recording).map_err(image::ImageError::IoError)?;
+
+    let scene = rail_scene();
+    let mut app = App::new(&scene, Path::new(SCENE_FILE));
+
+    let mut renderer = Renderer::headless(size, &scene);
+
+    for frame in recording.frames() {
+        app.frame(frame.events.iter().copied(), frame.delta_time, renderer.entities_mut());
+    }
+
+    renderer.draw_offscreen(&app.cameras());
+
+    let result = renderer.save_frame(output);
+
+    renderer.destroy();
+
+    result
 }
\ No newline at end of file
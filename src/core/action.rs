@@ -0,0 +1,334 @@
+use std::{collections::BTreeMap, fmt, fs, io, path::Path};
+
+use super::{event::MouseButton, input::{GamepadAxis, GamepadButton, InputManager, KeyCode}};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MouseAxis {
+    /// Horizontal cursor motion of the frame in pixels.
+    X,
+    /// Vertical cursor motion of the frame in pixels, positive down.
+    Y,
+    Wheel,
+}
+
+/// A physical input an action or axis can be bound to.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Binding {
+    Key(KeyCode),
+    Mouse(MouseButton),
+    MouseAxis(MouseAxis),
+    Gamepad(GamepadButton),
+    GamepadAxis(GamepadAxis),
+}
+
+impl Binding {
+    /// 1 or 0 for buttons, the raw value for analog inputs.
+    pub fn value(&self, input: &InputManager) -> f32 {
+        match *self {
+            Binding::MouseAxis(MouseAxis::X) => input.cursor_delta().x,
+            Binding::MouseAxis(MouseAxis::Y) => input.cursor_delta().y,
+            Binding::MouseAxis(MouseAxis::Wheel) => input.scroll_delta(),
+            Binding::GamepadAxis(axis) => input.gamepad_axis(axis),
+            _ => if self.is_down(input) { 1.0 } else { 0.0 },
+        }
+    }
+
+    /// Analog inputs count as held once pushed past half of their range.
+    pub fn is_down(&self, input: &InputManager) -> bool {
+        match *self {
+            Binding::Key(key) => input.is_down(key),
+            Binding::Mouse(button) => input.is_mouse_down(button),
+            Binding::Gamepad(button) => input.is_gamepad_down(button),
+            Binding::MouseAxis(_) | Binding::GamepadAxis(_) => self.value(input).abs() > 0.5,
+        }
+    }
+
+    pub fn just_pressed(&self, input: &InputManager) -> bool {
+        match *self {
+            Binding::Key(key) => input.just_pressed(key),
+            Binding::Mouse(button) => input.mouse_just_pressed(button),
+            Binding::Gamepad(button) => input.gamepad_just_pressed(button),
+            Binding::MouseAxis(_) | Binding::GamepadAxis(_) => false,
+        }
+    }
+
+    pub fn just_released(&self, input: &InputManager) -> bool {
+        match *self {
+            Binding::Key(key) => input.just_released(key),
+            Binding::Mouse(button) => input.mouse_just_released(button),
+            Binding::Gamepad(button) => input.gamepad_just_released(button),
+            Binding::MouseAxis(_) | Binding::GamepadAxis(_) => false,
+        }
+    }
+
+    fn parse(text: &str) -> Option<Self> {
+        let (device, name) = text.split_once(char::is_whitespace)?;
+        let name = name.trim();
+
+        match device {
+            "key" => KeyCode::from_name(name).map(Binding::Key),
+            "mouse" => match name {
+                "Left" => Some(Binding::Mouse(MouseButton::Left)),
+                "Right" => Some(Binding::Mouse(MouseButton::Right)),
+                "Middle" => Some(Binding::Mouse(MouseButton::Middle)),
+                _ => name.strip_prefix("Button")?.parse().ok().map(|extra| Binding::Mouse(MouseButton::Other(extra))),
+            },
+            "mouse_axis" => match name {
+                "X" => Some(Binding::MouseAxis(MouseAxis::X)),
+                "Y" => Some(Binding::MouseAxis(MouseAxis::Y)),
+                "Wheel" => Some(Binding::MouseAxis(MouseAxis::Wheel)),
+                _ => None,
+            },
+            "gamepad" => GamepadButton::from_name(name).map(Binding::Gamepad),
+            "gamepad_axis" => GamepadAxis::from_name(name).map(Binding::GamepadAxis),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Binding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Binding::Key(key) => write!(f, "key {key:?}"),
+            Binding::Mouse(MouseButton::Other(extra)) => write!(f, "mouse Button{extra}"),
+            Binding::Mouse(button) => write!(f, "mouse {button:?}"),
+            Binding::MouseAxis(axis) => write!(f, "mouse_axis {axis:?}"),
+            Binding::Gamepad(button) => write!(f, "gamepad {button:?}"),
+            Binding::GamepadAxis(axis) => write!(f, "gamepad_axis {axis:?}"),
+        }
+    }
+}
+
+/// Named actions and axes over the raw input, so game code does not depend on key codes.
+///
+/// Bindings are stored as text, one entry per line:
+/// ```text
+/// # comment
+/// action jump = key Space, gamepad South
+/// axis move_forward = key W 1, key S -1, gamepad_axis LeftStickY 1
+/// ```
+/// An axis adds up the value of each binding times its scale.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ActionMap {
+    actions: BTreeMap<String, Vec<Binding>>,
+    axes: BTreeMap<String, Vec<(Binding, f32)>>,
+}
+
+impl ActionMap {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn load(path: &Path) -> io::Result<Self> {
+        Self::parse(&fs::read_to_string(path)?)
+    }
+
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        fs::write(path, self.to_string())
+    }
+
+    pub fn parse(text: &str) -> io::Result<Self> {
+        let mut map = Self::new();
+
+        for (number, line) in text.lines().enumerate() {
+            let line = line.split('#').next().unwrap_or_default().trim();
+            if line.is_empty() {
+                continue;
+            }
+
+            let invalid = |reason: &str| io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("line {}: {reason}: {line}", number + 1)
+            );
+
+            let (head, bindings) = line.split_once('=').ok_or_else(|| invalid("missing '='"))?;
+            let (kind, name) = head.trim().split_once(char::is_whitespace).ok_or_else(|| invalid("missing name"))?;
+            let name = name.trim();
+
+            let bindings = bindings.split(',').map(str::trim).filter(|binding| !binding.is_empty());
+
+            match kind {
+                "action" => {
+                    let bindings = bindings
+                        .map(|binding| Binding::parse(binding).ok_or_else(|| invalid("unknown binding")))
+                        .collect::<io::Result<Vec<_>>>()?;
+                    map.actions.insert(name.to_owned(), bindings);
+                }
+                "axis" => {
+                    let bindings = bindings
+                        .map(|binding| Self::parse_scaled(binding).ok_or_else(|| invalid("unknown binding")))
+                        .collect::<io::Result<Vec<_>>>()?;
+                    map.axes.insert(name.to_owned(), bindings);
+                }
+                _ => return Err(invalid("expected 'action' or 'axis'")),
+            }
+        }
+
+        Ok(map)
+    }
+
+    /// A binding optionally followed by its scale, which defaults to 1.
+    fn parse_scaled(text: &str) -> Option<(Binding, f32)> {
+        if let Some((binding, scale)) = text.rsplit_once(char::is_whitespace) {
+            if let Ok(scale) = scale.parse() {
+                return Binding::parse(binding.trim()).map(|binding| (binding, scale));
+            }
+        }
+
+        Binding::parse(text).map(|binding| (binding, 1.0))
+    }
+
+    pub fn bind_action(&mut self, name: &str, binding: Binding) {
+        self.actions.entry(name.to_owned()).or_default().push(binding);
+    }
+
+    pub fn bind_axis(&mut self, name: &str, binding: Binding, scale: f32) {
+        self.axes.entry(name.to_owned()).or_default().push((binding, scale));
+    }
+
+    /// Replace every binding of an action, e.g. from a key rebinding menu.
+    pub fn rebind_action(&mut self, name: &str, bindings: Vec<Binding>) {
+        self.actions.insert(name.to_owned(), bindings);
+    }
+
+    pub fn rebind_axis(&mut self, name: &str, bindings: Vec<(Binding, f32)>) {
+        self.axes.insert(name.to_owned(), bindings);
+    }
+
+    pub fn action_bindings(&self, name: &str) -> &[Binding] {
+        self.actions.get(name).map(Vec::as_slice).unwrap_or_default()
+    }
+
+    pub fn axis_bindings(&self, name: &str) -> &[(Binding, f32)] {
+        self.axes.get(name).map(Vec::as_slice).unwrap_or_default()
+    }
+
+    /// True while any binding of the action is held, unknown actions are never down.
+    pub fn is_down(&self, input: &InputManager, name: &str) -> bool {
+        self.action_bindings(name).iter().any(|binding| binding.is_down(input))
+    }
+
+    /// True on the frame the first binding of the action went down.
+    pub fn just_pressed(&self, input: &InputManager, name: &str) -> bool {
+        let bindings = self.action_bindings(name);
+
+        bindings.iter().any(|binding| binding.just_pressed(input))
+            && !bindings.iter().any(|binding| binding.is_down(input) && !binding.just_pressed(input))
+    }
+
+    /// True on the frame the last held binding of the action went up.
+    pub fn just_released(&self, input: &InputManager, name: &str) -> bool {
+        let bindings = self.action_bindings(name);
+
+        bindings.iter().any(|binding| binding.just_released(input))
+            && !bindings.iter().any(|binding| binding.is_down(input))
+    }
+
+    /// Sum of the scaled bindings of the axis, unknown axes read 0.
+    pub fn axis(&self, input: &InputManager, name: &str) -> f32 {
+        self.axis_bindings(name).iter()
+            .map(|(binding, scale)| binding.value(input) * scale)
+            .sum()
+    }
+}
+
+impl fmt::Display for ActionMap {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (name, bindings) in &self.actions {
+            let bindings = bindings.iter().map(Binding::to_string).collect::<Vec<_>>();
+            writeln!(f, "action {name} = {}", bindings.join(", "))?;
+        }
+
+        for (name, bindings) in &self.axes {
+            let bindings = bindings.iter().map(|(binding, scale)| format!("{binding} {scale}")).collect::<Vec<_>>();
+            writeln!(f, "axis {name} = {}", bindings.join(", "))?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BINDINGS: &str = "\
+# movement
+action jump = key Space, gamepad South
+action fire = mouse Left, mouse Button2
+axis move_forward = key W 1, key S -1, gamepad_axis LeftStickY
+axis look_x = mouse_axis X 0.1, gamepad_axis RightStickX 2.5
+";
+
+    #[test]
+    fn parse_then_save_round_trips() {
+        let map = ActionMap::parse(BINDINGS).unwrap();
+
+        assert_eq!(map.action_bindings("jump"), [Binding::Key(KeyCode::Space), Binding::Gamepad(GamepadButton::South)]);
+        assert_eq!(map.action_bindings("fire"), [Binding::Mouse(MouseButton::Left), Binding::Mouse(MouseButton::Other(2))]);
+        assert_eq!(map.axis_bindings("move_forward"), [
+            (Binding::Key(KeyCode::W), 1.0),
+            (Binding::Key(KeyCode::S), -1.0),
+            (Binding::GamepadAxis(GamepadAxis::LeftStickY), 1.0),
+        ]);
+        assert_eq!(map.axis_bindings("look_x"), [
+            (Binding::MouseAxis(MouseAxis::X), 0.1),
+            (Binding::GamepadAxis(GamepadAxis::RightStickX), 2.5),
+        ]);
+
+        assert_eq!(ActionMap::parse(&map.to_string()).unwrap(), map);
+    }
+
+    #[test]
+    fn parse_reports_the_line() {
+        let error = ActionMap::parse("action jump = key Space\naction fire = key Nope").unwrap_err();
+
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+        assert!(error.to_string().starts_with("line 2: unknown binding"));
+    }
+
+    #[test]
+    fn lookup_through_bindings() {
+        let map = ActionMap::parse(BINDINGS).unwrap();
+        let mut input = InputManager::new();
+
+        assert!(!map.is_down(&input, "jump"));
+        assert!(!map.is_down(&input, "unknown"));
+        assert_eq!(map.axis(&input, "unknown"), 0.0);
+
+        input.gamepad_down(GamepadButton::South);
+        assert!(map.is_down(&input, "jump"));
+        assert!(map.just_pressed(&input, "jump"));
+
+        // a second binding going down while the first is held is not a new press.
+        input.begin_frame();
+        input.key_down(KeyCode::Space);
+        assert!(map.is_down(&input, "jump"));
+        assert!(!map.just_pressed(&input, "jump"));
+
+        input.begin_frame();
+        input.gamepad_up(GamepadButton::South);
+        assert!(!map.just_released(&input, "jump"));
+        input.begin_frame();
+        input.key_up(KeyCode::Space);
+        assert!(map.just_released(&input, "jump"));
+
+        input.key_down(KeyCode::W);
+        input.gamepad_axis_moved(GamepadAxis::LeftStickY, 0.5);
+        assert_eq!(map.axis(&input, "move_forward"), 1.5);
+        input.key_down(KeyCode::S);
+        assert_eq!(map.axis(&input, "move_forward"), 0.5);
+    }
+
+    #[test]
+    fn rebinding_replaces_the_bindings() {
+        let mut map = ActionMap::parse(BINDINGS).unwrap();
+        let mut input = InputManager::new();
+
+        map.rebind_action("jump", vec![Binding::Key(KeyCode::J)]);
+        input.key_down(KeyCode::Space);
+        assert!(!map.is_down(&input, "jump"));
+        input.key_down(KeyCode::J);
+        assert!(map.is_down(&input, "jump"));
+    }
+}
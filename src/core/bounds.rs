@@ -0,0 +1,312 @@
+use cgmath::{InnerSpace, Matrix, Matrix4, Vector3, Vector4, Zero};
+
+/// Axis aligned bounding box.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Aabb {
+    pub min: Vector3<f32>,
+    pub max: Vector3<f32>,
+}
+
+impl Aabb {
+    /// Smallest box around `points`, a box at the origin when there are none.
+    pub fn from_points(points: impl IntoIterator<Item = Vector3<f32>>) -> Self {
+        let mut points = points.into_iter();
+        let first = match points.next() {
+            Some(point) => point,
+            None => return Self { min: Vector3::zero(), max: Vector3::zero() },
+        };
+
+        points.fold(Self { min: first, max: first }, |aabb, point| Self {
+            min: Vector3::new(aabb.min.x.min(point.x), aabb.min.y.min(point.y), aabb.min.z.min(point.z)),
+            max: Vector3::new(aabb.max.x.max(point.x), aabb.max.y.max(point.y), aabb.max.z.max(point.z)),
+        })
+    }
+
+    pub fn center(&self) -> Vector3<f32> {
+        (self.min + self.max) * 0.5
+    }
+
+    /// Half of the size along each axis.
+    pub fn extents(&self) -> Vector3<f32> {
+        (self.max - self.min) * 0.5
+    }
+
+    /// Box around this one after `transform`, which grows with any rotation.
+    pub fn transform(&self, transform: &Matrix4<f32>) -> Self {
+        let center = (transform * self.center().extend(1.0)).truncate();
+        let extents = self.extents();
+
+        let extents = Vector3::new(
+            transform.row(0).truncate().map(f32::abs).dot(extents),
+            transform.row(1).truncate().map(f32::abs).dot(extents),
+            transform.row(2).truncate().map(f32::abs).dot(extents),
+        );
+
+        Self {
+            min: center - extents,
+            max: center + extents,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BoundingSphere {
+    pub center: Vector3<f32>,
+    pub radius: f32,
+}
+
+impl BoundingSphere {
+    /// Sphere centered on the box of `points` that holds every point.
+    pub fn from_points(points: &[Vector3<f32>]) -> Self {
+        let center = Aabb::from_points(points.iter().copied()).center();
+        let radius = points.iter()
+            .map(|point| (point - center).magnitude())
+            .fold(0.0, f32::max);
+
+        Self { center, radius }
+    }
+
+    /// Sphere after `transform`, scaled by its largest axis scale.
+    pub fn transform(&self, transform: &Matrix4<f32>) -> Self {
+        let scale = transform.x.truncate().magnitude()
+            .max(transform.y.truncate().magnitude())
+            .max(transform.z.truncate().magnitude());
+
+        Self {
+            center: (transform * self.center.extend(1.0)).truncate(),
+            radius: self.radius * scale,
+        }
+    }
+}
+
+/// Plane of the points where `normal.dot(point) + distance` is 0, the normal side is in front.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Plane {
+    pub normal: Vector3<f32>,
+    pub distance: f32,
+}
+
+impl Plane {
+    fn from_vector(plane: Vector4<f32>) -> Self {
+        let normal = plane.truncate();
+        let length = normal.magnitude();
+
+        // a plane at infinity has no normal, it keeps its sign so everything stays in front.
+        if length <= f32::EPSILON {
+            return Self { normal: Vector3::zero(), distance: plane.w };
+        }
+
+        Self {
+            normal: normal / length,
+            distance: plane.w / length,
+        }
+    }
+
+    /// Signed distance, positive in front of the plane.
+    pub fn distance_to(&self, point: Vector3<f32>) -> f32 {
+        self.normal.dot(point) + self.distance
+    }
+}
+
+/// Volume a camera sees, bounded by six planes facing inwards.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Frustum {
+    /// Left, right, top, bottom, near and far, in clip space terms.
+    pub planes: [Plane; 6],
+}
+
+impl Frustum {
+    /// Planes of a projection * view matrix mapping into Vulkan clip space, where
+    /// -w <= x, y <= w and 0 <= z <= w. Works for either depth convention.
+    pub fn from_matrix(projection_view: &Matrix4<f32>) -> Self {
+        let row = |i| projection_view.row(i);
+
+        Self {
+            planes: [
+                Plane::from_vector(row(3) + row(0)),
+                Plane::from_vector(row(3) - row(0)),
+                Plane::from_vector(row(3) + row(1)),
+                Plane::from_vector(row(3) - row(1)),
+                Plane::from_vector(row(2)),
+                Plane::from_vector(row(3) - row(2)),
+            ],
+        }
+    }
+
+    pub fn contains_point(&self, point: Vector3<f32>) -> bool {
+        self.planes.iter().all(|plane| plane.distance_to(point) >= 0.0)
+    }
+
+    /// False only when the sphere is fully behind one of the planes.
+    pub fn intersects_sphere(&self, sphere: &BoundingSphere) -> bool {
+        self.planes.iter().all(|plane| plane.distance_to(sphere.center) >= -sphere.radius)
+    }
+
+    /// False only when the box is fully behind one of the planes, boxes near a corner
+    /// of the frustum may pass without being visible.
+    pub fn intersects_aabb(&self, aabb: &Aabb) -> bool {
+        self.planes.iter().all(|plane| {
+            // the corner farthest along the normal.
+            let corner = Vector3::new(
+                if plane.normal.x >= 0.0 { aabb.max.x } else { aabb.min.x },
+                if plane.normal.y >= 0.0 { aabb.max.y } else { aabb.min.y },
+                if plane.normal.z >= 0.0 { aabb.max.z } else { aabb.min.z },
+            );
+
+            plane.distance_to(corner) >= 0.0
+        })
+    }
+}
+
+/// Half line from `origin` along `direction`, points on it are `origin + direction * t` for t >= 0.
+///
+/// The direction is not required to be normalized, hits are reported in multiples of it.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Ray {
+    pub origin: Vector3<f32>,
+    pub direction: Vector3<f32>,
+}
+
+impl Ray {
+    pub fn at(&self, t: f32) -> Vector3<f32> {
+        self.origin + self.direction * t
+    }
+
+    /// Ray after `transform`, a hit at `t` stays at `t` in the new space.
+    pub fn transform(&self, transform: &Matrix4<f32>) -> Self {
+        Self {
+            origin: (transform * self.origin.extend(1.0)).truncate(),
+            direction: (transform * self.direction.extend(0.0)).truncate(),
+        }
+    }
+
+    /// Closest `t` where the ray enters the box, 0 when it starts inside.
+    pub fn intersect_aabb(&self, aabb: &Aabb) -> Option<f32> {
+        let mut t_min = 0.0_f32;
+        let mut t_max = f32::INFINITY;
+
+        for axis in 0..3 {
+            let inverse = 1.0 / self.direction[axis];
+            let t0 = (aabb.min[axis] - self.origin[axis]) * inverse;
+            let t1 = (aabb.max[axis] - self.origin[axis]) * inverse;
+
+            // a ray parallel to the slab gives nan when it starts on its border, which counts as a hit.
+            if !t0.is_nan() && !t1.is_nan() {
+                t_min = t_min.max(t0.min(t1));
+                t_max = t_max.min(t0.max(t1));
+            }
+        }
+
+        (t_min <= t_max).then_some(t_min)
+    }
+
+    /// `t` and barycentric weights of `a`, `b` and `c` where the ray crosses the triangle,
+    /// from either side.
+    pub fn intersect_triangle(&self, a: Vector3<f32>, b: Vector3<f32>, c: Vector3<f32>) -> Option<(f32, Vector3<f32>)> {
+        // möller-trumbore.
+        let edge1 = b - a;
+        let edge2 = c - a;
+
+        let p = self.direction.cross(edge2);
+        let determinant = edge1.dot(p);
+        if determinant.abs() <= f32::EPSILON {
+            return None;
+        }
+        let inverse = 1.0 / determinant;
+
+        let to_origin = self.origin - a;
+        let u = to_origin.dot(p) * inverse;
+        if !(0.0..=1.0).contains(&u) {
+            return None;
+        }
+
+        let q = to_origin.cross(edge1);
+        let v = self.direction.dot(q) * inverse;
+        if v < 0.0 || u + v > 1.0 {
+            return None;
+        }
+
+        let t = edge2.dot(q) * inverse;
+        (t >= 0.0).then(|| (t, Vector3::new(1.0 - u - v, u, v)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cgmath::{assert_abs_diff_eq, Deg, Vector2};
+
+    use crate::core::camera::{Camera, DepthConvention, Projection};
+
+    fn unit_box() -> Aabb {
+        Aabb::from_points([Vector3::new(-1.0, -1.0, -1.0), Vector3::new(1.0, 1.0, 1.0)])
+    }
+
+    #[test]
+    fn aabb_transform_covers_rotated_box() {
+        let transform = Matrix4::from_translation(Vector3::new(5.0, 0.0, 0.0)) * Matrix4::from_angle_y(Deg(45.0));
+        let aabb = unit_box().transform(&transform);
+
+        let half_diagonal = 2.0_f32.sqrt();
+        assert_abs_diff_eq!(aabb.min, Vector3::new(5.0 - half_diagonal, -1.0, -half_diagonal), epsilon = 1e-5);
+        assert_abs_diff_eq!(aabb.max, Vector3::new(5.0 + half_diagonal, 1.0, half_diagonal), epsilon = 1e-5);
+    }
+
+    #[test]
+    fn frustum_planes_bound_the_view() {
+        let extent = Vector2 { x: 800, y: 600 };
+
+        for projection in [
+            Projection::Perspective { fovy: Deg(60.0), near: 0.1, far: 50.0 },
+            Projection::Orthographic { height: 10.0, near: 0.1, far: 50.0 },
+            Projection::ReversedZInfinite { fovy: Deg(60.0), near: 0.1 },
+        ] {
+            let mut camera = Camera::new();
+            camera.set_projection(projection);
+            let frustum = camera.frustum(extent);
+
+            assert!(frustum.contains_point(Vector3::new(0.0, 0.0, -10.0)), "{projection:?}");
+            assert!(!frustum.contains_point(Vector3::new(0.0, 0.0, 10.0)), "{projection:?}");
+            assert!(!frustum.contains_point(Vector3::new(0.0, 100.0, -10.0)), "{projection:?}");
+            assert!(!frustum.contains_point(Vector3::new(-100.0, 0.0, -10.0)), "{projection:?}");
+            assert_eq!(
+                frustum.contains_point(Vector3::new(0.0, 0.0, -1000.0)),
+                projection.depth_convention() == DepthConvention::Reversed,
+                "{projection:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn culling_keeps_boxes_crossing_a_plane() {
+        let frustum = Camera::new().frustum(Vector2 { x: 800, y: 600 });
+
+        let inside = unit_box().transform(&Matrix4::from_translation(Vector3::new(0.0, 0.0, -10.0)));
+        let crossing_near = unit_box();
+        let behind = unit_box().transform(&Matrix4::from_translation(Vector3::new(0.0, 0.0, 10.0)));
+
+        assert!(frustum.intersects_aabb(&inside));
+        assert!(frustum.intersects_aabb(&crossing_near));
+        assert!(!frustum.intersects_aabb(&behind));
+
+        let sphere = BoundingSphere::from_points(&[behind.min, behind.max]);
+        assert!(!frustum.intersects_sphere(&sphere));
+    }
+
+    #[test]
+    fn ray_hits_box_and_triangle() {
+        let ray = Ray { origin: Vector3::new(0.25, 0.25, 5.0), direction: Vector3::new(0.0, 0.0, -2.0) };
+
+        assert_eq!(ray.intersect_aabb(&unit_box()), Some(2.0));
+        assert_eq!(ray.intersect_aabb(&unit_box().transform(&Matrix4::from_translation(Vector3::new(3.0, 0.0, 0.0)))), None);
+
+        let (t, barycentrics) = ray
+            .intersect_triangle(Vector3::new(0.0, 0.0, 1.0), Vector3::new(1.0, 0.0, 1.0), Vector3::new(0.0, 1.0, 1.0))
+            .unwrap();
+        assert_abs_diff_eq!(t, 2.0);
+        assert_abs_diff_eq!(barycentrics, Vector3::new(0.5, 0.25, 0.25));
+        assert_abs_diff_eq!(ray.at(t), Vector3::new(0.25, 0.25, 1.0));
+
+        assert!(ray.intersect_triangle(Vector3::new(1.0, 1.0, 1.0), Vector3::new(2.0, 1.0, 1.0), Vector3::new(1.0, 2.0, 1.0)).is_none());
+    }
+}
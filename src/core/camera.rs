@@ -1,60 +1,594 @@
-use cgmath::{Angle, Deg, Matrix4, SquareMatrix, Vector2, Vector3};
+use ash::vk;
+use cgmath::{Angle, Deg, InnerSpace, Matrix3, Matrix4, One, Quaternion, Rad, Rotation, Rotation3, SquareMatrix, Vector2, Vector3, Vector4, Zero};
 
+use super::{action::ActionMap, bounds::{Frustum, Ray}, input::InputManager, window::CursorMode};
+
+/// Camera matrices as the vertex shader reads them from its uniform buffer.
+#[repr(C)]
 #[derive(Clone, Copy)]
 pub struct ProjectionViewObject {
-    pub(crate) view: Matrix4<f32>,
-    pub(crate) proj: Matrix4<f32>
+    pub view: Matrix4<f32>,
+    pub proj: Matrix4<f32>
+}
+
+/// How depth values are laid out in the depth buffer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DepthConvention {
+    /// 0 at the near plane and 1 at the far plane.
+    Standard,
+    /// 1 at the near plane and 0 at the far plane, which spreads float precision evenly over distance.
+    Reversed,
+}
+
+impl DepthConvention {
+    /// Depth test that keeps the closest fragment.
+    pub(crate) fn compare_op(self) -> vk::CompareOp {
+        match self {
+            DepthConvention::Standard => vk::CompareOp::LESS,
+            DepthConvention::Reversed => vk::CompareOp::GREATER,
+        }
+    }
+
+    /// Depth the buffer is cleared to, the farthest possible value.
+    pub(crate) fn clear_depth(self) -> f32 {
+        match self {
+            DepthConvention::Standard => 1.0,
+            DepthConvention::Reversed => 0.0,
+        }
+    }
+}
+
+/// Every projection maps into Vulkan clip space: x right, y down and depth in 0..1.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Projection {
+    Perspective { fovy: Deg<f32>, near: f32, far: f32 },
+    /// `height` is the world size covered vertically, the width follows the aspect ratio.
+    Orthographic { height: f32, near: f32, far: f32 },
+    /// Perspective with the far plane at infinity and depth reversed.
+    ReversedZInfinite { fovy: Deg<f32>, near: f32 },
+}
+
+impl Projection {
+    pub fn depth_convention(&self) -> DepthConvention {
+        match self {
+            Projection::Perspective { .. } | Projection::Orthographic { .. } => DepthConvention::Standard,
+            Projection::ReversedZInfinite { .. } => DepthConvention::Reversed,
+        }
+    }
+
+    /// Projection matrix for a right handed view space looking down -z.
+    pub fn matrix(&self, aspect: f32) -> Matrix4<f32> {
+        assert!(aspect.abs() > f32::EPSILON);
+
+        let mut projection_matrix = Matrix4::zero();
+
+        match *self {
+            Projection::Perspective { fovy, near, far } => {
+                let tan_half_fovy = (fovy / 2.0).tan();
+                projection_matrix[0][0] = 1.0 / (aspect * tan_half_fovy);
+                projection_matrix[1][1] = 1.0 / tan_half_fovy;
+                projection_matrix[2][2] = far / (near - far);
+                projection_matrix[2][3] = -1.0;
+                projection_matrix[3][2] = -(far * near) / (far - near);
+            }
+            Projection::Orthographic { height, near, far } => {
+                projection_matrix[0][0] = 2.0 / (height * aspect);
+                projection_matrix[1][1] = 2.0 / height;
+                projection_matrix[2][2] = 1.0 / (near - far);
+                projection_matrix[3][2] = near / (near - far);
+                projection_matrix[3][3] = 1.0;
+            }
+            Projection::ReversedZInfinite { fovy, near } => {
+                let tan_half_fovy = (fovy / 2.0).tan();
+                projection_matrix[0][0] = 1.0 / (aspect * tan_half_fovy);
+                projection_matrix[1][1] = 1.0 / tan_half_fovy;
+                projection_matrix[2][3] = -1.0;
+                projection_matrix[3][2] = near;
+            }
+        }
+
+        // vulkan clip space has y pointing down.
+        projection_matrix[1][1] *= -1.0;
+
+        projection_matrix
+    }
+}
+
+impl Default for Projection {
+    fn default() -> Self {
+        Projection::Perspective { fovy: Deg(45.0), near: 0.1, far: 100.0 }
+    }
+}
+
+/// Part of the render target a camera draws into, in 0..1 from the top left corner.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Viewport {
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
 }
 
+impl Viewport {
+    pub const FULL: Viewport = Viewport { x: 0.0, y: 0.0, width: 1.0, height: 1.0 };
+
+    /// Rectangle in pixels of a target of size `extent`, as offset and size.
+    pub fn pixels(&self, extent: Vector2<u32>) -> (Vector2<i32>, Vector2<u32>) {
+        let offset = Vector2 {
+            x: (self.x * extent.x as f32).round() as i32,
+            y: (self.y * extent.y as f32).round() as i32,
+        };
+        let size = Vector2 {
+            x: ((self.width * extent.x as f32).round() as u32).max(1),
+            y: ((self.height * extent.y as f32).round() as u32).max(1),
+        };
+
+        (offset, size)
+    }
+
+    /// Whether the pixel `point` of a target of size `extent` lies in the viewport.
+    pub fn contains(&self, point: Vector2<f32>, extent: Vector2<u32>) -> bool {
+        let (offset, size) = self.pixels(extent);
+
+        point.x >= offset.x as f32 && point.x < (offset.x + size.x as i32) as f32
+            && point.y >= offset.y as f32 && point.y < (offset.y + size.y as i32) as f32
+    }
+}
+
+impl Default for Viewport {
+    fn default() -> Self {
+        Self::FULL
+    }
+}
+
+/// Right handed camera looking down its local -Z axis, with +Y up.
+#[derive(Debug, Clone)]
 pub struct Camera {
-    pub(crate) position: Vector3<f32>, 
-    pub(crate) rotation: Vector3<f32>,
+    pub(crate) position: Vector3<f32>,
+    /// Rotation from camera space to world space.
+    pub(crate) orientation: Quaternion<f32>,
 
-    pub(crate) fovy: Deg<f32>, 
-    pub(crate) aspect: f32, 
-    pub(crate) near: f32, 
-    pub(crate) far: f32
+    pub(crate) projection: Projection,
+    pub(crate) viewport: Viewport,
 }
 
 impl Camera {
-    pub fn new(extent: Vector2<u32>) -> Camera {
+    pub fn new() -> Camera {
         Camera {
-            position: Vector3 { x: 1.0, y: 1.0, z: 1.0 },
-            rotation: Vector3 { x: 1.0, y: 1.0, z: 1.0 },
+            position: Vector3::zero(),
+            orientation: Quaternion::one(),
 
-            fovy: Deg(45.0),
-            aspect: extent.x as f32 / extent.y as f32,
-            near: 0.1,
-            far: 100.0,
+            projection: Projection::default(),
+            viewport: Viewport::FULL,
         }
     }
 
-    pub fn get_view(&self) -> Matrix4<f32> {
-        let mut rotation_matrix = Matrix4::identity();
-        let translate_matrix = Matrix4::from_translation(self.position);
+    pub fn position(&self) -> Vector3<f32> {
+        self.position
+    }
 
-        rotation_matrix += Matrix4::from_angle_x(Deg(self.rotation.x));
-		rotation_matrix += Matrix4::from_angle_y(Deg(self.rotation.y));
-		rotation_matrix += Matrix4::from_angle_z(Deg(self.rotation.z));
+    pub fn set_position(&mut self, position: Vector3<f32>) {
+        self.position = position;
+    }
+
+    pub fn projection(&self) -> Projection {
+        self.projection
+    }
+
+    pub fn set_projection(&mut self, projection: Projection) {
+        self.projection = projection;
+    }
+
+    pub fn viewport(&self) -> Viewport {
+        self.viewport
+    }
+
+    pub fn set_viewport(&mut self, viewport: Viewport) {
+        self.viewport = viewport;
+    }
+
+    /// Aspect ratio of the viewport on a target of size `extent`.
+    pub fn aspect(&self, extent: Vector2<u32>) -> f32 {
+        let (_, size) = self.viewport.pixels(extent);
+
+        size.x as f32 / size.y as f32
+    }
+
+    pub fn orientation(&self) -> Quaternion<f32> {
+        self.orientation
+    }
+
+    pub fn set_orientation(&mut self, orientation: Quaternion<f32>) {
+        self.orientation = orientation.normalize();
+    }
+
+    /// Turn the camera towards `target`, keeping its right vector horizontal to `up`.
+    /// Nothing changes when the target is the camera position or straight along `up`.
+    pub fn look_at(&mut self, target: Vector3<f32>, up: Vector3<f32>) {
+        let forward = target - self.position;
+        let right = forward.cross(up);
+        if forward.magnitude2() <= f32::EPSILON || right.magnitude2() <= f32::EPSILON {
+            return;
+        }
+
+        let forward = forward.normalize();
+        let right = right.normalize();
+        let up = right.cross(forward);
+
+        self.orientation = Quaternion::from(Matrix3::from_cols(right, up, -forward)).normalize();
+    }
+
+    pub fn forward(&self) -> Vector3<f32> {
+        self.orientation.rotate_vector(-Vector3::unit_z())
+    }
+
+    pub fn right(&self) -> Vector3<f32> {
+        self.orientation.rotate_vector(Vector3::unit_x())
+    }
+
+    pub fn up(&self) -> Vector3<f32> {
+        self.orientation.rotate_vector(Vector3::unit_y())
+    }
+
+    /// Set the orientation from euler angles, applied roll first, then pitch, then yaw.
+    /// Yaw turns around world +Y, pitch tilts up around the camera right axis and roll
+    /// banks around the view direction.
+    pub fn set_yaw_pitch_roll(&mut self, yaw: Deg<f32>, pitch: Deg<f32>, roll: Deg<f32>) {
+        self.orientation = Quaternion::from_angle_y(yaw)
+            * Quaternion::from_angle_x(pitch)
+            * Quaternion::from_angle_z(roll);
+    }
+
+    /// Euler angles of the orientation, inverse of `set_yaw_pitch_roll` with pitch in -90..90.
+    pub fn yaw_pitch_roll(&self) -> (Deg<f32>, Deg<f32>, Deg<f32>) {
+        let forward = self.forward();
+
+        let yaw = Rad::atan2(-forward.x, -forward.z);
+        // atan2 keeps its precision close to straight up or down, where asin loses it.
+        let pitch = Rad::atan2(forward.y, (forward.x * forward.x + forward.z * forward.z).sqrt());
+        let roll = Rad::atan2(self.right().y, self.up().y);
+
+        (yaw.into(), pitch.into(), roll.into())
+    }
+
+    pub fn set_yaw(&mut self, yaw: Deg<f32>) {
+        let (_, pitch, roll) = self.yaw_pitch_roll();
+        self.set_yaw_pitch_roll(yaw, pitch, roll);
+    }
+
+    pub fn set_pitch(&mut self, pitch: Deg<f32>) {
+        let (yaw, _, roll) = self.yaw_pitch_roll();
+        self.set_yaw_pitch_roll(yaw, pitch, roll);
+    }
+
+    pub fn set_roll(&mut self, roll: Deg<f32>) {
+        let (yaw, pitch, _) = self.yaw_pitch_roll();
+        self.set_yaw_pitch_roll(yaw, pitch, roll);
+    }
+
+    /// World to camera transform, the inverse of the camera placement.
+    pub fn get_view(&self) -> Matrix4<f32> {
+        let rotation_matrix = Matrix4::from(self.orientation.invert());
+        let translate_matrix = Matrix4::from_translation(-self.position);
 
         rotation_matrix * translate_matrix
     }
 
-    pub fn get_projection(&self) -> Matrix4<f32> {     
-        //assert!(glm::abs(aspect - std::numeric_limits<float>::epsilon()) > 0.0f);
-        
-        let mut projection_matrix = Matrix4::identity();
-        
-        let tan_half_fovy = (self.fovy / 2.0).tan();
-        projection_matrix[0][0] = 1.0 / (self.aspect * tan_half_fovy);
-        projection_matrix[1][1] = 1.0 / (tan_half_fovy);
-        projection_matrix[2][2] = self.far / (self.far - self.near);
-        projection_matrix[2][3] = 1.0;
-        projection_matrix[3][2] = -(self.far * self.near) / (self.far - self.near);
+    /// Projection for the current size of the render target, so the aspect ratio follows resizes.
+    pub fn get_projection(&self, extent: Vector2<u32>) -> Matrix4<f32> {
+        self.projection.matrix(self.aspect(extent))
+    }
 
-        //invert the y axis for vulkan
-        //projection_matrix[1][1] = projection_matrix[1][1] * -1.0;
+    /// Planes of what the camera sees on a target of size `extent`, in world space.
+    pub fn frustum(&self, extent: Vector2<u32>) -> Frustum {
+        Frustum::from_matrix(&(self.get_projection(extent) * self.get_view()))
+    }
 
-        projection_matrix
+    /// World space ray through the pixel `point` of a target of size `extent`, starting on the
+    /// near plane. `point` is measured from the top left corner of the target, not the viewport.
+    pub fn screen_point_to_ray(&self, point: Vector2<f32>, extent: Vector2<u32>) -> Ray {
+        let (offset, size) = self.viewport.pixels(extent);
+        let x = (point.x - offset.x as f32) / size.x as f32 * 2.0 - 1.0;
+        let y = (point.y - offset.y as f32) / size.y as f32 * 2.0 - 1.0;
+
+        let inverse = (self.get_projection(extent) * self.get_view())
+            .invert()
+            .expect("Failed to invert the camera matrix!");
+        let unproject = |depth: f32| {
+            let point = inverse * Vector4::new(x, y, depth, 1.0);
+            point.truncate() / point.w
+        };
+
+        // halfway in depth is a finite distance even with the far plane at infinity.
+        let near = unproject(1.0 - self.projection.depth_convention().clear_depth());
+        let middle = unproject(0.5);
+
+        Ray {
+            origin: near,
+            direction: (middle - near).normalize(),
+        }
+    }
+
+    /// Camera placement in the world, the inverse of the view matrix.
+    pub fn transform(&self) -> Matrix4<f32> {
+        Matrix4::from_translation(self.position) * Matrix4::from(self.orientation)
+    }
+}
+
+impl Default for Camera {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Moves a camera from the frame input, see `FlyController`, `OrbitController` and `FollowController`.
+///
+/// Controllers read the `move_*`, `look_*`, `orbit` and `zoom` entries of the action map,
+/// so they follow the user bindings.
+pub trait CameraController {
+    /// Turn the camera once per rendered frame, since mouse look is measured per frame.
+    fn look(&mut self, _camera: &mut Camera, _input: &InputManager, _actions: &ActionMap) {}
+
+    /// Move the camera once per fixed step, so it travels the same at any frame rate.
+    fn step(&mut self, _camera: &mut Camera, _input: &InputManager, _actions: &ActionMap, _step: f32) {}
+
+    /// Give the controller the world position of the entity it tracks, called every frame before `step`.
+    fn attach(&mut self, _target: Vector3<f32>) {}
+
+    /// Cursor mode the window should be in while this controller is active.
+    fn cursor_mode(&self) -> CursorMode {
+        CursorMode::Normal
+    }
+}
+
+/// Free flying first person camera, moving along the view direction and turned by the look axes.
+pub struct FlyController {
+    /// Units per second.
+    pub speed: f32,
+    /// Degrees per unit of look input, a pixel for the mouse.
+    pub sensitivity: f32,
+}
+
+impl Default for FlyController {
+    fn default() -> Self {
+        Self {
+            speed: 3.0,
+            sensitivity: 0.1,
+        }
+    }
+}
+
+impl CameraController for FlyController {
+    fn look(&mut self, camera: &mut Camera, input: &InputManager, actions: &ActionMap) {
+        // stop short of straight up or down, where yaw can not be told apart from roll.
+        const MAX_PITCH: f32 = 89.0;
+
+        let (yaw, pitch, _) = camera.yaw_pitch_roll();
+        let yaw = yaw - Deg(actions.axis(input, "look_x") * self.sensitivity);
+        let pitch = Deg((pitch.0 - actions.axis(input, "look_y") * self.sensitivity).clamp(-MAX_PITCH, MAX_PITCH));
+        camera.set_yaw_pitch_roll(yaw, pitch, Deg(0.0));
+    }
+
+    fn step(&mut self, camera: &mut Camera, input: &InputManager, actions: &ActionMap, step: f32) {
+        let movement = camera.right() * actions.axis(input, "move_right")
+            + Vector3::unit_y() * actions.axis(input, "move_up")
+            + camera.forward() * actions.axis(input, "move_forward");
+
+        camera.position += movement * self.speed * step;
     }
-}
\ No newline at end of file
+
+    fn cursor_mode(&self) -> CursorMode {
+        CursorMode::Locked
+    }
+}
+
+/// Circles a target point, turned by the look axes while `orbit` is held and zoomed by the `zoom` axis.
+pub struct OrbitController {
+    pub target: Vector3<f32>,
+    pub distance: f32,
+    pub min_distance: f32,
+    pub max_distance: f32,
+    pub yaw: Deg<f32>,
+    pub pitch: Deg<f32>,
+    /// Degrees per unit of look input.
+    pub sensitivity: f32,
+    /// Fraction of the distance covered per unit of zoom input.
+    pub zoom_speed: f32,
+}
+
+impl OrbitController {
+    /// Orbit around `target` starting from where the camera is now.
+    pub fn new(camera: &Camera, target: Vector3<f32>) -> Self {
+        let offset = camera.position - target;
+        let distance = offset.magnitude().max(f32::EPSILON);
+
+        Self {
+            target,
+            distance,
+            min_distance: 0.5,
+            max_distance: 50.0,
+            yaw: Rad::atan2(offset.x, offset.z).into(),
+            pitch: Rad::asin((-offset.y / distance).clamp(-1.0, 1.0)).into(),
+            sensitivity: 0.3,
+            zoom_speed: 0.1,
+        }
+    }
+}
+
+impl CameraController for OrbitController {
+    /// Orbiting is all look input, so the position follows the turn within the same frame.
+    fn look(&mut self, camera: &mut Camera, input: &InputManager, actions: &ActionMap) {
+        const MAX_PITCH: f32 = 89.0;
+
+        if actions.is_down(input, "orbit") {
+            self.yaw -= Deg(actions.axis(input, "look_x") * self.sensitivity);
+            self.pitch = Deg((self.pitch.0 - actions.axis(input, "look_y") * self.sensitivity).clamp(-MAX_PITCH, MAX_PITCH));
+        }
+
+        let zoom = actions.axis(input, "zoom");
+        self.distance = (self.distance * (1.0 - zoom * self.zoom_speed)).clamp(self.min_distance, self.max_distance);
+
+        camera.set_yaw_pitch_roll(self.yaw, self.pitch, Deg(0.0));
+        camera.position = self.target - camera.forward() * self.distance;
+    }
+}
+
+/// Trails an entity at a fixed offset, easing towards it instead of snapping.
+pub struct FollowController {
+    /// Camera position relative to the followed entity, in world space.
+    pub offset: Vector3<f32>,
+    /// How fast the camera closes the gap, higher is stiffer. Roughly the inverse of the catch up time in seconds.
+    pub stiffness: f32,
+    target: Option<Vector3<f32>>,
+}
+
+impl FollowController {
+    pub fn new(offset: Vector3<f32>) -> Self {
+        Self {
+            offset,
+            stiffness: 4.0,
+            target: None,
+        }
+    }
+}
+
+impl CameraController for FollowController {
+    fn step(&mut self, camera: &mut Camera, _input: &InputManager, _actions: &ActionMap, step: f32) {
+        let Some(target) = self.target else {
+            return;
+        };
+
+        // exponential smoothing, so the result does not depend on the step length.
+        let blend = 1.0 - (-self.stiffness * step).exp();
+        camera.position += (target + self.offset - camera.position) * blend;
+        camera.look_at(target, Vector3::unit_y());
+    }
+
+    fn attach(&mut self, target: Vector3<f32>) {
+        self.target = Some(target);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cgmath::{assert_abs_diff_eq, EuclideanSpace, Point3};
+
+    fn camera_at(position: Vector3<f32>) -> Camera {
+        let mut camera = Camera::new();
+        camera.set_position(position);
+        camera
+    }
+
+    fn look_at_rh(eye: Vector3<f32>, target: Vector3<f32>, up: Vector3<f32>) -> Matrix4<f32> {
+        Matrix4::look_at_rh(Point3::from_vec(eye), Point3::from_vec(target), up)
+    }
+
+    #[test]
+    fn look_at_matches_cgmath() {
+        let cases = [
+            (Vector3::new(0.0, 0.0, 5.0), Vector3::zero(), Vector3::unit_y()),
+            (Vector3::new(3.0, 2.0, -4.0), Vector3::new(-1.0, 0.5, 2.0), Vector3::unit_y()),
+            (Vector3::new(-6.0, 8.0, 1.0), Vector3::new(0.0, 1.0, 1.0), Vector3::unit_y()),
+            (Vector3::new(1.0, -2.0, 3.0), Vector3::new(4.0, 5.0, 6.0), Vector3::unit_z()),
+        ];
+
+        for (eye, target, up) in cases {
+            let mut camera = camera_at(eye);
+            camera.look_at(target, up);
+
+            assert_abs_diff_eq!(camera.get_view(), look_at_rh(eye, target, up), epsilon = 1e-5);
+        }
+    }
+
+    #[test]
+    fn basis_vectors_follow_look_at() {
+        let eye = Vector3::new(2.0, 3.0, 4.0);
+        let target = Vector3::new(-1.0, 0.0, 1.0);
+
+        let mut camera = camera_at(eye);
+        camera.look_at(target, Vector3::unit_y());
+
+        assert_abs_diff_eq!(camera.forward(), (target - eye).normalize(), epsilon = 1e-5);
+        assert_abs_diff_eq!(camera.right().y, 0.0, epsilon = 1e-5);
+        assert_abs_diff_eq!(camera.right().cross(camera.up()), -camera.forward(), epsilon = 1e-5);
+    }
+
+    #[test]
+    fn view_is_inverse_of_transform() {
+        let mut camera = camera_at(Vector3::new(1.0, 2.0, 3.0));
+        camera.set_yaw_pitch_roll(Deg(30.0), Deg(-20.0), Deg(10.0));
+
+        assert_abs_diff_eq!(camera.get_view() * camera.transform(), Matrix4::identity(), epsilon = 1e-5);
+    }
+
+    /// Depth and y of a view space point after projection and perspective divide.
+    fn project(projection: Projection, z: f32) -> (f32, f32) {
+        let clip = projection.matrix(1.0) * Vector4::new(0.0, 1.0, z, 1.0);
+        (clip.z / clip.w, clip.y / clip.w)
+    }
+
+    #[test]
+    fn projections_follow_depth_convention() {
+        let perspective = Projection::Perspective { fovy: Deg(60.0), near: 0.5, far: 50.0 };
+        assert_abs_diff_eq!(project(perspective, -0.5).0, 0.0, epsilon = 1e-5);
+        assert_abs_diff_eq!(project(perspective, -50.0).0, 1.0, epsilon = 1e-5);
+
+        let orthographic = Projection::Orthographic { height: 10.0, near: 0.5, far: 50.0 };
+        assert_abs_diff_eq!(project(orthographic, -0.5).0, 0.0, epsilon = 1e-5);
+        assert_abs_diff_eq!(project(orthographic, -50.0).0, 1.0, epsilon = 1e-5);
+
+        let reversed = Projection::ReversedZInfinite { fovy: Deg(60.0), near: 0.5 };
+        assert_abs_diff_eq!(project(reversed, -0.5).0, 1.0, epsilon = 1e-5);
+        assert!(project(reversed, -1.0e6).0 < 1e-5);
+        assert_eq!(reversed.depth_convention(), DepthConvention::Reversed);
+
+        // up in view space is down in vulkan clip space.
+        for projection in [perspective, orthographic, reversed] {
+            assert!(project(projection, -5.0).1 < 0.0);
+        }
+    }
+
+    #[test]
+    fn yaw_pitch_roll_round_trip() {
+        let mut camera = camera_at(Vector3::zero());
+        camera.set_yaw_pitch_roll(Deg(120.0), Deg(35.0), Deg(-15.0));
+
+        let (yaw, pitch, roll) = camera.yaw_pitch_roll();
+        assert_abs_diff_eq!(yaw.0, 120.0, epsilon = 1e-3);
+        assert_abs_diff_eq!(pitch.0, 35.0, epsilon = 1e-3);
+        assert_abs_diff_eq!(roll.0, -15.0, epsilon = 1e-3);
+    }
+
+    #[test]
+    fn yaw_pitch_matches_look_at() {
+        let eye = Vector3::zero();
+
+        // yaw 90 turns from -z to -x, pitch 45 then tilts up.
+        let mut camera = camera_at(eye);
+        camera.set_yaw_pitch_roll(Deg(90.0), Deg(45.0), Deg(0.0));
+
+        let target = Vector3::new(-1.0, 1.0, 0.0);
+        assert_abs_diff_eq!(camera.get_view(), look_at_rh(eye, target, Vector3::unit_y()), epsilon = 1e-5);
+    }
+
+    #[test]
+    fn screen_center_ray_follows_forward() {
+        let extent = Vector2 { x: 800, y: 600 };
+
+        for projection in [Projection::default(), Projection::ReversedZInfinite { fovy: Deg(45.0), near: 0.1 }] {
+            let mut camera = camera_at(Vector3::new(1.0, 2.0, 3.0));
+            camera.set_projection(projection);
+            camera.look_at(Vector3::new(-2.0, 0.0, -1.0), Vector3::unit_y());
+
+            let ray = camera.screen_point_to_ray(Vector2::new(400.0, 300.0), extent);
+            assert_abs_diff_eq!(ray.direction, camera.forward(), epsilon = 1e-4);
+            assert_abs_diff_eq!(ray.origin, camera.position() + camera.forward() * 0.1, epsilon = 1e-4);
+
+            // the top left corner is up and to the left of the view direction.
+            let corner = camera.screen_point_to_ray(Vector2::new(0.0, 0.0), extent);
+            assert!(corner.direction.dot(camera.up()) > 0.0);
+            assert!(corner.direction.dot(camera.right()) < 0.0);
+        }
+    }
+}
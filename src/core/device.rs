@@ -42,7 +42,16 @@ pub struct GraphicDevice {
 
 impl GraphicDevice {
     pub fn new(instance: &ash::Instance, surface: &Surface) -> Self {
-        let physical_device = Self::pick_physical_device(instance, &surface);
+        Self::create(instance, Some(surface))
+    }
+
+    /// Device without presentation support, used to render into offscreen targets.
+    pub fn headless(instance: &ash::Instance) -> Self {
+        Self::create(instance, None)
+    }
+
+    fn create(instance: &ash::Instance, surface: Option<&Surface>) -> Self {
+        let physical_device = Self::pick_physical_device(instance, surface);
         let physical_device_memory_properties =
             unsafe { instance.get_physical_device_memory_properties(physical_device) };
 
@@ -65,7 +74,7 @@ impl GraphicDevice {
 
     fn pick_physical_device(
         instance: &ash::Instance,
-        surface: &Surface
+        surface: Option<&Surface>
     ) -> vk::PhysicalDevice {
         let physical_devices = unsafe {
             instance
@@ -90,7 +99,7 @@ impl GraphicDevice {
     fn is_physical_device_suitable(
         instance: &ash::Instance,
         physical_device: vk::PhysicalDevice,
-        surface: &Surface
+        surface: Option<&Surface>
     ) -> bool {
         let device_features = unsafe { instance.get_physical_device_features(physical_device) };
         let indices = Self::find_queue_family(instance, physical_device, surface);
@@ -98,14 +107,18 @@ impl GraphicDevice {
         let is_queue_family_supported = indices.is_complete();
         let is_device_extension_supported = Self::check_device_extension_support(
             instance,
-            physical_device
+            physical_device,
+            surface.is_some()
         );
-        let is_swapchain_supported = if is_device_extension_supported {
-            let swapchain_support =
-                SwapChain::query_swapchain_support(physical_device, surface);
-            !swapchain_support.formats.is_empty() && !swapchain_support.present_modes.is_empty()
-        } else {
-            false
+        let is_swapchain_supported = match surface {
+            Some(surface) if is_device_extension_supported => {
+                let swapchain_support =
+                    SwapChain::query_swapchain_support(physical_device, surface);
+                !swapchain_support.formats.is_empty() && !swapchain_support.present_modes.is_empty()
+            }
+            Some(_) => false,
+            // nothing is presented, so there is no swapchain to support.
+            None => true,
         };
         let is_support_sampler_anisotropy = device_features.sampler_anisotropy == 1;
 
@@ -118,7 +131,7 @@ impl GraphicDevice {
     fn create_logical_device(
         instance: &ash::Instance,
         physical_device: vk::PhysicalDevice,
-        surface: &Surface,
+        surface: Option<&Surface>,
     ) -> (ash::Device, QueueFamilyIndices) {
         let indices = Self::find_queue_family(instance, physical_device, surface);
 
@@ -144,9 +157,11 @@ impl GraphicDevice {
             ..Default::default() // default just enable no feature.
         };
 
-        let enable_extension_names = [
-            ash::extensions::khr::Swapchain::name().as_ptr(), // currently just enable the Swapchain extension.
-        ];
+        let enable_extension_names = if surface.is_some() {
+            vec![ash::extensions::khr::Swapchain::name().as_ptr()] // currently just enable the Swapchain extension.
+        } else {
+            vec![]
+        };
 
         let device_create_info = vk::DeviceCreateInfo {
             s_type: vk::StructureType::DEVICE_CREATE_INFO,
@@ -172,7 +187,7 @@ impl GraphicDevice {
     fn find_queue_family(
         instance: &ash::Instance,
         physical_device: vk::PhysicalDevice,
-        surface: &Surface,
+        surface: Option<&Surface>,
     ) -> QueueFamilyIndices {
         let queue_families =
             unsafe { instance.get_physical_device_queue_family_properties(physical_device) };
@@ -187,14 +202,19 @@ impl GraphicDevice {
                 queue_family_indices.graphics_family = Some(index);
             }
 
-            let is_present_support = unsafe {
-                surface.loader.get_physical_device_surface_support(
-                    physical_device,
-                    index as u32,
-                    surface.surface,
-                )
-            }
-            .unwrap();
+            // without a surface the graphics queue doubles as the present queue,
+            // it is never used to present anything.
+            let is_present_support = match surface {
+                Some(surface) => unsafe {
+                    surface.loader.get_physical_device_surface_support(
+                        physical_device,
+                        index as u32,
+                        surface.surface,
+                    )
+                }
+                .unwrap(),
+                None => queue_family_indices.graphics_family == Some(index),
+            };
 
             if queue_family.queue_count > 0 && is_present_support {
                 queue_family_indices.present_family = Some(index);
@@ -212,8 +232,13 @@ impl GraphicDevice {
 
     fn check_device_extension_support(
         instance: &ash::Instance,
-        physical_device: vk::PhysicalDevice
+        physical_device: vk::PhysicalDevice,
+        is_presenting: bool
     ) -> bool {
+        if !is_presenting {
+            return true;
+        }
+
         let available_extensions = unsafe {
             instance
                 .enumerate_device_extension_properties(physical_device)
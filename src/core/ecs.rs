@@ -0,0 +1,461 @@
+use std::{any::{Any, TypeId}, collections::HashMap, marker::PhantomData};
+
+use super::entity::{EntityId, EntityJoin};
+
+/// Data attached to entities, any `'static` type can be a component.
+pub trait Component: 'static {}
+
+impl<T: 'static> Component for T {}
+
+/// Components of one type, packed densely and looked up by entity index.
+pub struct SparseSet<T> {
+    /// Dense position of the component of each entity index.
+    sparse: Vec<Option<usize>>,
+    ids: Vec<EntityId>,
+    data: Vec<T>,
+    /// Set when a component is inserted or borrowed mutably, cleared by its consumer.
+    is_changed: Vec<bool>,
+}
+
+impl<T> SparseSet<T> {
+    fn new() -> Self {
+        Self {
+            sparse: Vec::new(),
+            ids: Vec::new(),
+            data: Vec::new(),
+            is_changed: Vec::new(),
+        }
+    }
+
+    /// Set the component of `id`, returning the one it replaces.
+    fn insert(&mut self, id: EntityId, value: T) -> Option<T> {
+        let index = id.index();
+        if let Some(dense) = self.dense(index) {
+            self.ids[dense] = id;
+            self.is_changed[dense] = true;
+            return Some(std::mem::replace(&mut self.data[dense], value));
+        }
+
+        if self.sparse.len() <= index {
+            self.sparse.resize(index + 1, None);
+        }
+        self.sparse[index] = Some(self.data.len());
+        self.ids.push(id);
+        self.data.push(value);
+        self.is_changed.push(true);
+
+        None
+    }
+
+    fn remove(&mut self, index: usize) -> Option<T> {
+        let dense = self.sparse.get_mut(index)?.take()?;
+
+        self.ids.swap_remove(dense);
+        self.is_changed.swap_remove(dense);
+        let value = self.data.swap_remove(dense);
+
+        // the last component moved into the hole.
+        if let Some(moved) = self.ids.get(dense) {
+            self.sparse[moved.index()] = Some(dense);
+        }
+
+        Some(value)
+    }
+
+    fn dense(&self, index: usize) -> Option<usize> {
+        *self.sparse.get(index)?
+    }
+
+    pub fn get(&self, index: usize) -> Option<&T> {
+        Some(&self.data[self.dense(index)?])
+    }
+
+    /// Mutable access, marks the component as changed.
+    pub fn get_mut(&mut self, index: usize) -> Option<&mut T> {
+        let dense = self.dense(index)?;
+        self.is_changed[dense] = true;
+
+        Some(&mut self.data[dense])
+    }
+
+    pub fn is_changed(&self, index: usize) -> bool {
+        self.dense(index).is_some_and(|dense| self.is_changed[dense])
+    }
+
+    pub fn clear_changed(&mut self) {
+        self.is_changed.fill(false);
+    }
+
+    pub fn len(&self) -> usize {
+        self.data.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (EntityId, &T)> {
+        self.ids.iter().copied().zip(&self.data)
+    }
+}
+
+/// A `SparseSet` of any component type.
+pub trait Storage: Any {
+    fn remove_entity(&mut self, index: usize);
+    fn as_any(&self) -> &dyn Any;
+    fn as_any_mut(&mut self) -> &mut dyn Any;
+}
+
+impl<T: Component> Storage for SparseSet<T> {
+    fn remove_entity(&mut self, index: usize) {
+        self.remove(index);
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+/// Component storages of an `EntityJoin`, one per component type.
+#[derive(Default)]
+pub struct Components {
+    storages: HashMap<TypeId, Box<dyn Storage>>,
+}
+
+impl Components {
+    pub fn storage<T: Component>(&self) -> Option<&SparseSet<T>> {
+        self.storages.get(&TypeId::of::<T>())?.as_any().downcast_ref()
+    }
+
+    pub fn storage_mut<T: Component>(&mut self) -> Option<&mut SparseSet<T>> {
+        self.storages.get_mut(&TypeId::of::<T>())?.as_any_mut().downcast_mut()
+    }
+
+    pub(crate) fn insert<T: Component>(&mut self, id: EntityId, value: T) -> Option<T> {
+        self.storages.entry(TypeId::of::<T>())
+            .or_insert_with(|| Box::new(SparseSet::<T>::new()))
+            .as_any_mut()
+            .downcast_mut::<SparseSet<T>>()
+            .expect("Failed to downcast a component storage!")
+            .insert(id, value)
+    }
+
+    pub(crate) fn remove<T: Component>(&mut self, index: usize) -> Option<T> {
+        self.storage_mut::<T>()?.remove(index)
+    }
+
+    pub(crate) fn remove_entity(&mut self, index: usize) {
+        for storage in self.storages.values_mut() {
+            storage.remove_entity(index);
+        }
+    }
+
+    /// The storages a mutable query asks for, each handed out once.
+    fn borrow_mut<Q: Query>(&mut self) -> Vec<(TypeId, &mut Box<dyn Storage>)> {
+        let mut type_ids = Vec::new();
+        Q::type_ids(&mut type_ids);
+
+        for (i, type_id) in type_ids.iter().enumerate() {
+            assert!(!type_ids[..i].contains(type_id), "Failed to query, a component type appears twice!");
+        }
+
+        self.storages.iter_mut()
+            .filter(|(type_id, _)| type_ids.contains(type_id))
+            .map(|(type_id, storage)| (*type_id, storage))
+            .collect()
+    }
+}
+
+/// Components a query reads or writes: `&T`, `&mut T` or a tuple of them.
+///
+/// ```ignore
+/// for (id, (transform, velocity)) in entities.query_mut::<(&mut Transform, &Velocity)>() { ... }
+/// ```
+pub trait Query {
+    type Item<'a>;
+    type State<'a>;
+
+    fn type_ids(type_ids: &mut Vec<TypeId>);
+
+    /// Take the storages of the query out of `storages`, `None` when one of them does not exist.
+    fn init<'a>(storages: &mut Vec<(TypeId, &'a mut Box<dyn Storage>)>) -> Option<Self::State<'a>>;
+
+    /// Components of the entity at `index`, `None` when it lacks any of them.
+    ///
+    /// # Safety
+    /// Each index may be fetched at most once per state, mutable items of an index alias otherwise.
+    unsafe fn fetch<'a>(state: &mut Self::State<'a>, index: usize) -> Option<Self::Item<'a>>;
+}
+
+/// Queries that only read, so they can run on a shared `EntityJoin`.
+pub trait ReadOnlyQuery: Query {
+    fn init_shared(components: &Components) -> Option<Self::State<'_>>;
+}
+
+fn take_storage<'a, T: Component>(storages: &mut Vec<(TypeId, &'a mut Box<dyn Storage>)>) -> Option<&'a mut SparseSet<T>> {
+    let position = storages.iter().position(|(type_id, _)| *type_id == TypeId::of::<T>())?;
+    let (_, storage) = storages.swap_remove(position);
+
+    storage.as_any_mut().downcast_mut()
+}
+
+impl<T: Component> Query for &T {
+    type Item<'a> = &'a T;
+    type State<'a> = &'a SparseSet<T>;
+
+    fn type_ids(type_ids: &mut Vec<TypeId>) {
+        type_ids.push(TypeId::of::<T>());
+    }
+
+    fn init<'a>(storages: &mut Vec<(TypeId, &'a mut Box<dyn Storage>)>) -> Option<Self::State<'a>> {
+        take_storage(storages).map(|storage| &*storage)
+    }
+
+    unsafe fn fetch<'a>(state: &mut Self::State<'a>, index: usize) -> Option<Self::Item<'a>> {
+        state.get(index)
+    }
+}
+
+impl<T: Component> ReadOnlyQuery for &T {
+    fn init_shared(components: &Components) -> Option<Self::State<'_>> {
+        components.storage()
+    }
+}
+
+/// Write access into a storage without holding a unique borrow of it, so items of different
+/// entities can be alive at the same time.
+pub struct MutState<'a, T> {
+    sparse: &'a [Option<usize>],
+    data: *mut T,
+    is_changed: *mut bool,
+    marker: PhantomData<&'a mut T>,
+}
+
+impl<T: Component> Query for &mut T {
+    type Item<'a> = &'a mut T;
+    type State<'a> = MutState<'a, T>;
+
+    fn type_ids(type_ids: &mut Vec<TypeId>) {
+        type_ids.push(TypeId::of::<T>());
+    }
+
+    fn init<'a>(storages: &mut Vec<(TypeId, &'a mut Box<dyn Storage>)>) -> Option<Self::State<'a>> {
+        let SparseSet { sparse, data, is_changed, .. } = take_storage::<T>(storages)?;
+
+        Some(MutState {
+            sparse,
+            data: data.as_mut_ptr(),
+            is_changed: is_changed.as_mut_ptr(),
+            marker: PhantomData,
+        })
+    }
+
+    unsafe fn fetch<'a>(state: &mut Self::State<'a>, index: usize) -> Option<Self::Item<'a>> {
+        let dense = (*state.sparse.get(index)?)?;
+        *state.is_changed.add(dense) = true;
+
+        Some(&mut *state.data.add(dense))
+    }
+}
+
+macro_rules! impl_query {
+    ($($name:ident),+) => {
+        #[allow(non_snake_case)]
+        impl<$($name: Query),+> Query for ($($name,)+) {
+            type Item<'a> = ($($name::Item<'a>,)+);
+            type State<'a> = ($($name::State<'a>,)+);
+
+            fn type_ids(type_ids: &mut Vec<TypeId>) {
+                $($name::type_ids(type_ids);)+
+            }
+
+            fn init<'a>(storages: &mut Vec<(TypeId, &'a mut Box<dyn Storage>)>) -> Option<Self::State<'a>> {
+                Some(($($name::init(storages)?,)+))
+            }
+
+            unsafe fn fetch<'a>(state: &mut Self::State<'a>, index: usize) -> Option<Self::Item<'a>> {
+                let ($($name,)+) = state;
+                Some(($($name::fetch($name, index)?,)+))
+            }
+        }
+
+        impl<$($name: ReadOnlyQuery),+> ReadOnlyQuery for ($($name,)+) {
+            fn init_shared(components: &Components) -> Option<Self::State<'_>> {
+                Some(($($name::init_shared(components)?,)+))
+            }
+        }
+    };
+}
+
+impl_query!(A);
+impl_query!(A, B);
+impl_query!(A, B, C);
+impl_query!(A, B, C, D);
+impl_query!(A, B, C, D, E);
+impl_query!(A, B, C, D, E, F);
+
+impl EntityJoin {
+    /// Every entity with all the components of `Q`, in index order.
+    pub fn query<'a, Q: ReadOnlyQuery + 'a>(&'a self) -> impl Iterator<Item = (EntityId, Q::Item<'a>)> + 'a {
+        let mut state = Q::init_shared(self.components());
+
+        self.ids().filter_map(move |id| {
+            // each live index comes up once.
+            let item = unsafe { Q::fetch(state.as_mut()?, id.index()) }?;
+            Some((id, item))
+        })
+    }
+
+    /// Like `query`, `&mut` components are marked as changed as they are handed out.
+    pub fn query_mut<'a, Q: Query + 'a>(&'a mut self) -> impl Iterator<Item = (EntityId, Q::Item<'a>)> + 'a {
+        let (ids, components) = self.ids_and_components_mut();
+        let mut state = Q::init(&mut components.borrow_mut::<Q>());
+
+        ids.into_iter().filter_map(move |id| {
+            // each live index comes up once.
+            let item = unsafe { Q::fetch(state.as_mut()?, id.index()) }?;
+            Some((id, item))
+        })
+    }
+}
+
+/// Game logic run over the entities, see `Schedule`.
+pub trait System {
+    fn run(&mut self, entities: &mut EntityJoin, delta_time: f32);
+}
+
+impl<F: FnMut(&mut EntityJoin, f32)> System for F {
+    fn run(&mut self, entities: &mut EntityJoin, delta_time: f32) {
+        self(entities, delta_time)
+    }
+}
+
+/// Named systems run one after the other in a fixed order.
+#[derive(Default)]
+pub struct Schedule {
+    systems: Vec<(String, Box<dyn System>)>,
+}
+
+impl Schedule {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Run `system` after every system added so far.
+    pub fn add(&mut self, name: &str, system: impl System + 'static) {
+        self.systems.push((name.to_owned(), Box::new(system)));
+    }
+
+    /// Run `system` right before the system called `before`.
+    pub fn add_before(&mut self, before: &str, name: &str, system: impl System + 'static) {
+        let position = self.position(before).expect("Failed to find the system to run before!");
+        self.systems.insert(position, (name.to_owned(), Box::new(system)));
+    }
+
+    /// Run `system` right after the system called `after`.
+    pub fn add_after(&mut self, after: &str, name: &str, system: impl System + 'static) {
+        let position = self.position(after).expect("Failed to find the system to run after!");
+        self.systems.insert(position + 1, (name.to_owned(), Box::new(system)));
+    }
+
+    pub fn remove(&mut self, name: &str) -> bool {
+        let count = self.systems.len();
+        self.systems.retain(|(other, _)| other != name);
+
+        self.systems.len() != count
+    }
+
+    /// System names in the order they run.
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.systems.iter().map(|(name, _)| name.as_str())
+    }
+
+    pub fn run(&mut self, entities: &mut EntityJoin, delta_time: f32) {
+        for (_, system) in &mut self.systems {
+            system.run(entities, delta_time);
+        }
+    }
+
+    fn position(&self, name: &str) -> Option<usize> {
+        self.systems.iter().position(|(other, _)| other == name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::entity::{Entity, MeshRef, Transform};
+    use cgmath::Vector3;
+
+    struct Velocity(Vector3<f32>);
+
+    #[test]
+    fn queries_match_entities_with_every_component() {
+        let mut entities = EntityJoin::new();
+        let moving = entities.add(Entity::new().with(Velocity(Vector3::unit_x())).with(MeshRef(0)));
+        let still = entities.add(Entity::new().with(MeshRef(1)));
+        let ghost = entities.add(Entity::new().with(Velocity(Vector3::unit_y())));
+
+        let meshes: Vec<_> = entities.query::<(&Transform, &MeshRef)>().map(|(id, (_, mesh))| (id, *mesh)).collect();
+        assert_eq!(meshes, vec![(moving, MeshRef(0)), (still, MeshRef(1))]);
+
+        for (_, (transform, velocity)) in entities.query_mut::<(&mut Transform, &Velocity)>() {
+            transform.position += velocity.0;
+        }
+        assert_eq!(entities.component::<Transform>(moving).unwrap().position, Vector3::unit_x());
+        assert_eq!(entities.component::<Transform>(ghost).unwrap().position, Vector3::unit_y());
+        assert_eq!(entities.component::<Transform>(still).unwrap().position, Vector3::new(0.0, 0.0, 0.0));
+
+        // a mutable query marks what it hands out, the transform update consumes the marks.
+        assert!(entities.components().storage::<Transform>().unwrap().is_changed(moving.index()));
+        assert!(entities.update_transforms());
+        assert!(!entities.components().storage::<Transform>().unwrap().is_changed(moving.index()));
+        assert_eq!(entities.world_transform(moving).unwrap().w.truncate(), Vector3::unit_x());
+    }
+
+    #[test]
+    fn removing_keeps_other_components_in_place() {
+        let mut entities = EntityJoin::new();
+        let ids: Vec<_> = (0..4).map(|i| entities.add(Entity::new().with(MeshRef(i)))).collect();
+
+        entities.remove(ids[1]);
+        assert_eq!(entities.remove_component::<MeshRef>(ids[3]), Some(MeshRef(3)));
+        let added = entities.add(Entity::new().with(MeshRef(4)));
+
+        let meshes: Vec<_> = entities.query::<&MeshRef>().map(|(id, mesh)| (id, mesh.0)).collect();
+        assert_eq!(meshes, vec![(ids[0], 0), (added, 4), (ids[2], 2)]);
+        assert_eq!(entities.component::<MeshRef>(ids[1]), None);
+    }
+
+    #[test]
+    #[should_panic]
+    fn query_mut_refuses_aliasing() {
+        let mut entities = EntityJoin::new();
+        entities.add(Entity::new());
+
+        entities.query_mut::<(&mut Transform, &Transform)>().for_each(drop);
+    }
+
+    #[test]
+    fn systems_run_in_order() {
+        let mut schedule = Schedule::new();
+        schedule.add("physics", |_: &mut EntityJoin, _| {});
+        schedule.add("render_sync", |_: &mut EntityJoin, _| {});
+        schedule.add_before("physics", "input", |_: &mut EntityJoin, _| {});
+        schedule.add_after("physics", "collisions", |entities: &mut EntityJoin, delta_time| {
+            entities.add(Entity::new().with(Velocity(Vector3::new(delta_time, 0.0, 0.0))));
+        });
+
+        assert_eq!(schedule.names().collect::<Vec<_>>(), vec!["input", "physics", "collisions", "render_sync"]);
+        assert!(schedule.remove("render_sync"));
+        assert!(!schedule.remove("render_sync"));
+
+        let mut entities = EntityJoin::new();
+        schedule.run(&mut entities, 0.5);
+        assert_eq!(entities.query::<&Velocity>().map(|(_, velocity)| velocity.0.x).collect::<Vec<_>>(), vec![0.5]);
+    }
+}
@@ -1,70 +1,31 @@
-use cgmath::{Matrix4, Vector3, Vector4};
+use std::any::TypeId;
 
-pub trait Transform {
-    fn transform(&self) -> Matrix4<f32>;
-}
+use cgmath::{Matrix4, SquareMatrix, Vector3, Vector4, Zero};
 
-pub struct EntityJoin{
-    entities: Vec<Entity>
-}
+use super::{ecs::{Component, Components}, tween::Color};
 
-impl EntityJoin {
-    pub fn new() -> Self {
-        Self {
-            entities: Vec::new()
-        }
-    }
-
-    pub(crate) fn add(&mut self, entity: Entity) {
-        self.entities.push(entity)
-    }
-
-    pub(crate) fn get_transforms(&self) -> Vec<Matrix4<f32>> {
-        self.entities.iter().map(|x| -> Matrix4<f32> {
-                x.transform()
-            }
-        ).collect()
-    }
+/// Placement of an entity relative to its parent, or to the world for a root entity.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Transform {
+    pub position: Vector3<f32>,
+    pub scale: Vector3<f32>,
+    /// Tait-Bryan angles in radians.
+    pub rotation: Vector3<f32>,
 }
 
-pub struct Entity {
-    pub(crate) position: Vector3<f32>,
-    pub(crate) scale: Vector3<f32>,
-    pub(crate) rotation: Vector3<f32>,
-}
-
-impl Entity {
+impl Transform {
     pub fn new() -> Self {
         Self {
-            position: Vector3 { x: 1.0, y: 1.0, z: 1.0 },
+            position: Vector3::zero(),
             scale: Vector3 { x: 1.0, y: 1.0, z: 1.0 },
-            rotation: Vector3 { x: 1.0, y: 1.0, z: 1.0 },
+            rotation: Vector3::zero(),
         }
     }
 
-    /*
-    pub fn with_position(mut self, pos: Vector3<f32>) -> Self {
-        self.position = pos;
-        self
-    }
-
-    pub fn with_scale(mut self, scale: Vector3<f32>) -> Self {
-        self.scale = scale;
-        self
-    }
-
-    pub fn with_rotation(mut self, rot: Vector3<f32>) -> Self {
-        self.rotation = rot;
-        self
-    }
-    */
-}
-
-impl Transform for Entity {
     // Matrix corrsponds to Translate * Ry * Rx * Rz * Scale
     // Rotations correspond to Tait-bryan angles of Y(1), X(2), Z(3)
     // https://en.wikipedia.org/wiki/Euler_angles#Rotation_matrix
-    fn transform(&self) -> Matrix4<f32> {
+    pub fn matrix(&self) -> Matrix4<f32> {
         let c3 = self.rotation.z.cos();
         let s3 = self.rotation.z.sin();
         let c2 = self.rotation.x.cos();
@@ -99,4 +60,537 @@ impl Transform for Entity {
             }
         } 
     }
-}
\ No newline at end of file
+}
+
+impl Default for Transform {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Index of a mesh loaded by the renderer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MeshRef(pub usize);
+
+/// Index of a material loaded by the renderer, for now a texture.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MaterialRef(pub usize);
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LightKind {
+    /// Parallel rays along the entity's -Z axis, like the sun.
+    Directional,
+    /// Light from the entity position fading out at `range`.
+    Point { range: f32 },
+}
+
+/// Light placed and turned by the transform of its entity. Nothing is shaded with it yet.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Light {
+    pub kind: LightKind,
+    pub color: Color,
+    pub intensity: f32,
+}
+
+/// Handle to an entity of an `EntityJoin`. Removing the entity makes every copy of its handle
+/// stale, lookups with it return `None` even after the slot is reused.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct EntityId {
+    index: u32,
+    generation: u32,
+}
+
+impl EntityId {
+    /// Slot of the entity, shared with whatever entity takes the slot after it is removed.
+    pub(crate) fn index(self) -> usize {
+        self.index as usize
+    }
+}
+
+struct Slot {
+    generation: u32,
+    entity: Option<Entity>,
+
+    parent: Option<usize>,
+    children: Vec<usize>,
+
+    world_transform: Matrix4<f32>,
+    is_dirty: bool,
+}
+
+/// Entities, their components and their hierarchy. Each entity places itself relative to its
+/// parent with its `Transform`, and its world matrix is the product of the transforms from the
+/// root down.
+///
+/// World matrices are cached and only recomputed by `update_transforms` for entities whose
+/// transform or parent changed, along with everything below them.
+pub struct EntityJoin{
+    slots: Vec<Slot>,
+    free_slots: Vec<usize>,
+
+    components: Components,
+}
+
+impl EntityJoin {
+    pub fn new() -> Self {
+        Self {
+            slots: Vec::new(),
+            free_slots: Vec::new(),
+
+            components: Components::default(),
+        }
+    }
+
+    /// Add a root entity along with its components.
+    pub fn add(&mut self, mut entity: Entity) -> EntityId {
+        let transform = entity.transform.take().unwrap_or_default();
+        let pending = std::mem::take(&mut entity.components);
+
+        let slot = Slot {
+            generation: 0,
+            entity: Some(entity),
+            parent: None,
+            children: Vec::new(),
+            world_transform: Matrix4::identity(),
+            is_dirty: true,
+        };
+
+        let index = match self.free_slots.pop() {
+            Some(index) => {
+                let generation = self.slots[index].generation;
+                self.slots[index] = Slot { generation, ..slot };
+                index
+            }
+            None => {
+                self.slots.push(slot);
+                self.slots.len() - 1
+            }
+        };
+
+        let id = self.id(index);
+        self.components.insert(id, transform);
+        for insert in pending {
+            insert(&mut self.components, id);
+        }
+
+        id
+    }
+
+    /// Add an entity placed relative to `parent`.
+    pub fn add_child(&mut self, parent: EntityId, entity: Entity) -> EntityId {
+        let id = self.add(entity);
+        self.reparent(id, parent);
+
+        id
+    }
+
+    /// Remove an entity along with its descendants and all their components, and return it.
+    pub fn remove(&mut self, id: EntityId) -> Option<Entity> {
+        let index = self.index(id)?;
+        self.detach(id);
+
+        let mut stack = vec![index];
+        while let Some(index) = stack.pop() {
+            let slot = &mut self.slots[index];
+            stack.append(&mut slot.children);
+
+            slot.generation = slot.generation.wrapping_add(1);
+            slot.parent = None;
+            if index != id.index() {
+                slot.entity = None;
+            }
+
+            self.components.remove_entity(index);
+            self.free_slots.push(index);
+        }
+
+        self.slots[index].entity.take()
+    }
+
+    pub fn contains(&self, id: EntityId) -> bool {
+        self.index(id).is_some()
+    }
+
+    pub fn get(&self, id: EntityId) -> Option<&Entity> {
+        self.slots[self.index(id)?].entity.as_ref()
+    }
+
+    pub fn get_mut(&mut self, id: EntityId) -> Option<&mut Entity> {
+        let index = self.index(id)?;
+
+        self.slots[index].entity.as_mut()
+    }
+
+    /// Attach a component to the entity, returning the one of the same type it replaces.
+    pub fn insert<T: Component>(&mut self, id: EntityId, component: T) -> Option<T> {
+        self.index(id)?;
+
+        self.components.insert(id, component)
+    }
+
+    /// Detach a component from the entity, without a `Transform` it sits at its parent.
+    pub fn remove_component<T: Component>(&mut self, id: EntityId) -> Option<T> {
+        let index = self.index(id)?;
+
+        let component = self.components.remove(index)?;
+        if TypeId::of::<T>() == TypeId::of::<Transform>() {
+            self.slots[index].is_dirty = true;
+        }
+
+        Some(component)
+    }
+
+    pub fn component<T: Component>(&self, id: EntityId) -> Option<&T> {
+        self.components.storage()?.get(self.index(id)?)
+    }
+
+    /// Mutable access to a component, a changed `Transform` moves the entity and its
+    /// descendants on the next update.
+    pub fn component_mut<T: Component>(&mut self, id: EntityId) -> Option<&mut T> {
+        let index = self.index(id)?;
+
+        self.components.storage_mut()?.get_mut(index)
+    }
+
+    pub fn components(&self) -> &Components {
+        &self.components
+    }
+
+    /// Live entities in index order.
+    pub fn ids(&self) -> impl Iterator<Item = EntityId> + '_ {
+        (0..self.slots.len())
+            .filter(|&index| self.slots[index].entity.is_some())
+            .map(|index| self.id(index))
+    }
+
+    pub(crate) fn ids_and_components_mut(&mut self) -> (Vec<EntityId>, &mut Components) {
+        (self.ids().collect(), &mut self.components)
+    }
+
+    /// First entity called `name`.
+    pub fn find(&self, name: &str) -> Option<EntityId> {
+        self.iter().find(|(_, entity)| entity.name() == Some(name)).map(|(id, _)| id)
+    }
+
+    pub fn tagged<'a>(&'a self, tag: &'a str) -> impl Iterator<Item = EntityId> + 'a {
+        self.iter().filter(move |(_, entity)| entity.has_tag(tag)).map(|(id, _)| id)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (EntityId, &Entity)> + '_ {
+        self.slots.iter().enumerate().filter_map(|(index, slot)| {
+            slot.entity.as_ref().map(|entity| (self.id(index), entity))
+        })
+    }
+
+    pub fn parent(&self, id: EntityId) -> Option<EntityId> {
+        self.slots[self.index(id)?].parent.map(|parent| self.id(parent))
+    }
+
+    /// Direct children of the entity, none for a stale handle.
+    pub fn children(&self, id: EntityId) -> impl Iterator<Item = EntityId> + '_ {
+        let children = match self.index(id) {
+            Some(index) => self.slots[index].children.as_slice(),
+            None => &[],
+        };
+
+        children.iter().map(|&child| self.id(child))
+    }
+
+    /// Move `id` under `parent`, keeping its local transform.
+    pub fn reparent(&mut self, id: EntityId, parent: EntityId) {
+        let index = self.index(id).expect("Failed to reparent a removed entity!");
+        let parent = self.index(parent).expect("Failed to reparent to a removed entity!");
+        assert!(
+            !self.is_ancestor_or_self(index, parent),
+            "Failed to reparent, the new parent is the entity itself or one of its children!"
+        );
+
+        self.detach(id);
+        self.slots[index].parent = Some(parent);
+        self.slots[parent].children.push(index);
+    }
+
+    /// Make `id` a root entity, its local transform becomes its world transform.
+    pub fn detach(&mut self, id: EntityId) {
+        let Some(index) = self.index(id) else {
+            return;
+        };
+
+        if let Some(parent) = self.slots[index].parent.take() {
+            self.slots[parent].children.retain(|&child| child != index);
+        }
+        self.slots[index].is_dirty = true;
+    }
+
+    fn index(&self, id: EntityId) -> Option<usize> {
+        let slot = self.slots.get(id.index())?;
+        (slot.generation == id.generation && slot.entity.is_some()).then_some(id.index())
+    }
+
+    fn id(&self, index: usize) -> EntityId {
+        EntityId {
+            index: index as u32,
+            generation: self.slots[index].generation,
+        }
+    }
+
+    fn is_ancestor_or_self(&self, ancestor: usize, mut index: usize) -> bool {
+        loop {
+            if index == ancestor {
+                return true;
+            }
+            match self.slots[index].parent {
+                Some(parent) => index = parent,
+                None => return false,
+            }
+        }
+    }
+
+    /// Recompute the world matrices of the entities that moved and of everything below them,
+    /// true when any of them changed. Entities without a `Transform` sit at their parent.
+    pub(crate) fn update_transforms(&mut self) -> bool {
+        let mut is_any_changed = false;
+
+        let transforms = self.components.storage::<Transform>();
+
+        let mut stack: Vec<(usize, bool)> = self.slots.iter().enumerate().rev()
+            .filter(|(_, slot)| slot.entity.is_some() && slot.parent.is_none())
+            .map(|(root, _)| (root, false))
+            .collect();
+        while let Some((index, is_parent_changed)) = stack.pop() {
+            let transform = transforms.and_then(|transforms| transforms.get(index));
+            let is_changed = is_parent_changed
+                || self.slots[index].is_dirty
+                || transforms.is_some_and(|transforms| transforms.is_changed(index));
+
+            if is_changed {
+                let parent_transform = match self.slots[index].parent {
+                    Some(parent) => self.slots[parent].world_transform,
+                    None => Matrix4::identity(),
+                };
+
+                let slot = &mut self.slots[index];
+                slot.world_transform = match transform {
+                    Some(transform) => parent_transform * transform.matrix(),
+                    None => parent_transform,
+                };
+                slot.is_dirty = false;
+                is_any_changed = true;
+            }
+
+            stack.extend(self.slots[index].children.iter().rev().map(|&child| (child, is_changed)));
+        }
+
+        if let Some(transforms) = self.components.storage_mut::<Transform>() {
+            transforms.clear_changed();
+        }
+
+        is_any_changed
+    }
+
+    /// World matrix of an entity as of the last `update_transforms`.
+    pub fn world_transform(&self, id: EntityId) -> Option<Matrix4<f32>> {
+        Some(self.slots[self.index(id)?].world_transform)
+    }
+}
+
+impl Default for EntityJoin {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Something placed in the world with a name, tags and components, built fluently:
+/// ```ignore
+/// Entity::new().with_name("cart").with_tag("vehicle").with_position(Vector3::new(0.0, 1.0, 0.0)).with(MeshRef(0))
+/// ```
+/// Every entity starts with a `Transform`, the components move into the `EntityJoin` it is added to.
+pub struct Entity {
+    name: Option<String>,
+    tags: Vec<String>,
+
+    /// Moved out into the `Transform` component when the entity is added.
+    transform: Option<Transform>,
+    components: Vec<InsertComponent>,
+}
+
+/// A component given to the builder, inserted once the entity has an id.
+type InsertComponent = Box<dyn FnOnce(&mut Components, EntityId)>;
+
+impl Entity {
+    pub fn new() -> Self {
+        Self {
+            name: None,
+            tags: Vec::new(),
+
+            transform: Some(Transform::new()),
+            components: Vec::new(),
+        }
+    }
+
+    pub fn with_name(mut self, name: &str) -> Self {
+        self.name = Some(name.to_owned());
+        self
+    }
+
+    pub fn with_tag(mut self, tag: &str) -> Self {
+        self.tags.push(tag.to_owned());
+        self
+    }
+
+    pub fn with<T: Component>(mut self, component: T) -> Self {
+        self.components.push(Box::new(move |components: &mut Components, id| {
+            components.insert(id, component);
+        }));
+        self
+    }
+
+    pub fn with_transform(mut self, transform: Transform) -> Self {
+        self.transform = Some(transform);
+        self
+    }
+
+    pub fn with_position(mut self, pos: Vector3<f32>) -> Self {
+        self.transform.get_or_insert_with(Transform::new).position = pos;
+        self
+    }
+
+    pub fn with_scale(mut self, scale: Vector3<f32>) -> Self {
+        self.transform.get_or_insert_with(Transform::new).scale = scale;
+        self
+    }
+
+    pub fn with_rotation(mut self, rot: Vector3<f32>) -> Self {
+        self.transform.get_or_insert_with(Transform::new).rotation = rot;
+        self
+    }
+
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    pub fn tags(&self) -> &[String] {
+        &self.tags
+    }
+
+    pub fn has_tag(&self, tag: &str) -> bool {
+        self.tags.iter().any(|other| other == tag)
+    }
+
+    pub fn add_tag(&mut self, tag: &str) {
+        if !self.has_tag(tag) {
+            self.tags.push(tag.to_owned());
+        }
+    }
+
+    pub fn remove_tag(&mut self, tag: &str) {
+        self.tags.retain(|other| other != tag);
+    }
+}
+
+impl Default for Entity {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cgmath::{assert_abs_diff_eq, Zero};
+
+    fn entity_at(position: Vector3<f32>) -> Entity {
+        Entity::new().with_position(position).with_rotation(Vector3::zero())
+    }
+
+    fn world_position(entities: &EntityJoin, id: EntityId) -> Vector3<f32> {
+        entities.world_transform(id).unwrap().w.truncate()
+    }
+
+    #[test]
+    fn children_follow_their_parent() {
+        let mut entities = EntityJoin::new();
+        let cart = entities.add(entity_at(Vector3::new(1.0, 0.0, 0.0)));
+        let wheel = entities.add_child(cart, entity_at(Vector3::new(0.0, -0.5, 0.0)));
+        let bolt = entities.add_child(wheel, entity_at(Vector3::new(0.0, 0.0, 0.1)));
+        entities.update_transforms();
+
+        assert_abs_diff_eq!(world_position(&entities, bolt), Vector3::new(1.0, -0.5, 0.1));
+        assert_eq!(entities.children(cart).collect::<Vec<_>>(), vec![wheel]);
+        assert_eq!(entities.parent(bolt), Some(wheel));
+
+        entities.component_mut::<Transform>(cart).unwrap().position = Vector3::new(3.0, 0.0, 0.0);
+        assert!(entities.update_transforms());
+        assert_abs_diff_eq!(world_position(&entities, bolt), Vector3::new(3.0, -0.5, 0.1));
+
+        assert!(!entities.update_transforms());
+    }
+
+    #[test]
+    fn removing_the_transform_moves_the_entity_to_its_parent() {
+        let mut entities = EntityJoin::new();
+        let cart = entities.add(entity_at(Vector3::new(1.0, 0.0, 0.0)));
+        let wheel = entities.add_child(cart, entity_at(Vector3::new(0.0, -0.5, 0.0)));
+        let bolt = entities.add_child(wheel, entity_at(Vector3::new(0.0, 0.0, 0.1)));
+        entities.update_transforms();
+
+        assert!(entities.remove_component::<Transform>(wheel).is_some());
+        assert!(entities.update_transforms());
+        assert_abs_diff_eq!(world_position(&entities, wheel), Vector3::new(1.0, 0.0, 0.0));
+        assert_abs_diff_eq!(world_position(&entities, bolt), Vector3::new(1.0, 0.0, 0.1));
+
+        assert!(entities.remove_component::<Transform>(wheel).is_none());
+        assert!(!entities.update_transforms());
+    }
+
+    #[test]
+    fn reparent_and_detach_keep_the_local_transform() {
+        let mut entities = EntityJoin::new();
+        let a = entities.add(entity_at(Vector3::new(1.0, 0.0, 0.0)));
+        let b = entities.add(entity_at(Vector3::new(0.0, 2.0, 0.0)));
+        let child = entities.add_child(a, entity_at(Vector3::new(0.0, 0.0, 1.0)));
+
+        entities.reparent(child, b);
+        entities.update_transforms();
+        assert_abs_diff_eq!(world_position(&entities, child), Vector3::new(0.0, 2.0, 1.0));
+        assert_eq!(entities.children(a).count(), 0);
+
+        entities.detach(child);
+        entities.update_transforms();
+        assert_abs_diff_eq!(world_position(&entities, child), Vector3::new(0.0, 0.0, 1.0));
+        assert_eq!(entities.parent(child), None);
+    }
+
+    #[test]
+    #[should_panic]
+    fn reparent_refuses_cycles() {
+        let mut entities = EntityJoin::new();
+        let parent = entities.add(Entity::new());
+        let child = entities.add_child(parent, Entity::new());
+
+        entities.reparent(parent, child);
+    }
+
+    #[test]
+    fn removed_handles_go_stale() {
+        let mut entities = EntityJoin::new();
+        let cart = entities.add(Entity::new().with_name("cart").with_tag("vehicle"));
+        let wheel = entities.add_child(cart, Entity::new().with_name("wheel"));
+        let rail = entities.add(Entity::new().with_name("rail"));
+
+        assert_eq!(entities.find("wheel"), Some(wheel));
+        assert_eq!(entities.tagged("vehicle").collect::<Vec<_>>(), vec![cart]);
+
+        assert_eq!(entities.remove(cart).and_then(|cart| cart.name().map(str::to_owned)), Some("cart".to_owned()));
+        assert!(!entities.contains(cart));
+        assert!(!entities.contains(wheel));
+        assert_eq!(entities.find("wheel"), None);
+        assert!(entities.remove(cart).is_none());
+
+        // the freed slots are reused without reviving the old handles.
+        let crate_box = entities.add(Entity::new().with_name("crate"));
+        assert!(entities.get(cart).is_none() && entities.get(wheel).is_none());
+        assert_eq!(entities.get(crate_box).and_then(Entity::name), Some("crate"));
+        assert_eq!(entities.get(rail).and_then(Entity::name), Some("rail"));
+    }
+}
@@ -0,0 +1,43 @@
+use cgmath::Vector2;
+
+use super::input::{GamepadAxis, GamepadButton, KeyCode};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MouseButton {
+    Left,
+    Right,
+    Middle,
+    Other(u8),
+}
+
+/// Window messages translated by the platform backend, drained once per frame.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Event {
+    /// New inner size of the window in pixels.
+    Resized(Vector2<u32>),
+    /// New position of the window on the desktop.
+    Moved(Vector2<i32>),
+    Focused(bool),
+    Minimized(bool),
+    /// Display scale relative to 96 DPI.
+    ScaleFactorChanged(f32),
+    CloseRequested,
+
+    KeyDown(KeyCode),
+    KeyUp(KeyCode),
+    /// Text input, after keyboard layout and modifiers are applied.
+    Char(char),
+
+    /// Cursor position relative to the top left corner of the window.
+    MouseMoved(Vector2<i32>),
+    /// Relative pointer motion in pixels, also reported while the cursor is locked.
+    MouseMotion(Vector2<f32>),
+    MouseDown(MouseButton),
+    MouseUp(MouseButton),
+    /// Wheel movement in notches, positive away from the user.
+    MouseWheel(f32),
+
+    GamepadDown(GamepadButton),
+    GamepadUp(GamepadButton),
+    GamepadAxisMoved(GamepadAxis, f32),
+}
@@ -0,0 +1,317 @@
+use super::{event::Event, input::{GamepadAxis, GamepadButton, GAMEPAD_AXES, GAMEPAD_BUTTONS}};
+
+const BUTTON_COUNT: usize = GAMEPAD_BUTTONS.len();
+const AXIS_COUNT: usize = GAMEPAD_AXES.len();
+
+/// Stick values closer to the center than this are reported as 0.
+const STICK_DEADZONE: f32 = 0.24;
+
+/// Buttons and axes of a pad at one moment, indexed like `GamepadButton` and `GamepadAxis`.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+struct PadState {
+    buttons: [bool; BUTTON_COUNT],
+    axes: [f32; AXIS_COUNT],
+}
+
+impl PadState {
+    fn set_button(&mut self, button: GamepadButton, is_down: bool) {
+        self.buttons[button as usize] = is_down;
+    }
+
+    fn set_axis(&mut self, axis: GamepadAxis, value: f32) {
+        self.axes[axis as usize] = value;
+    }
+
+    /// Events that turn `self` into `next`, a disconnected pad is the default state.
+    fn changes(&self, next: &PadState, events: &mut Vec<Event>) {
+        for index in 0..BUTTON_COUNT {
+            if self.buttons[index] != next.buttons[index] {
+                let button = GamepadButton::from_index(index).unwrap();
+                events.push(if next.buttons[index] { Event::GamepadDown(button) } else { Event::GamepadUp(button) });
+            }
+        }
+
+        for index in 0..AXIS_COUNT {
+            if self.axes[index] != next.axes[index] {
+                events.push(Event::GamepadAxisMoved(GamepadAxis::from_index(index).unwrap(), next.axes[index]));
+            }
+        }
+    }
+}
+
+/// Normalize a raw stick value to -1..1, applying the deadzone.
+fn stick(value: i16) -> f32 {
+    let value = (value as f32 / i16::MAX as f32).clamp(-1.0, 1.0);
+
+    if value.abs() < STICK_DEADZONE {
+        0.0
+    } else {
+        value.signum() * (value.abs() - STICK_DEADZONE) / (1.0 - STICK_DEADZONE)
+    }
+}
+
+/// First connected gamepad, polled by the window along with its messages.
+pub(crate) struct Gamepad {
+    state: PadState,
+    source: platform::Source,
+}
+
+impl Gamepad {
+    pub(crate) fn new() -> Self {
+        Self {
+            state: PadState::default(),
+            source: platform::Source::new(),
+        }
+    }
+
+    /// Push an event for every button and axis that changed since the last poll.
+    pub(crate) fn poll(&mut self, events: &mut Vec<Event>) {
+        let next = self.source.read();
+
+        self.state.changes(&next, events);
+        self.state = next;
+    }
+}
+
+#[cfg(windows)]
+mod platform {
+    use std::time::{Duration, Instant};
+    use windows::Win32::UI::Input::XboxController::*;
+
+    use super::{stick, GamepadAxis, GamepadButton, PadState, BUTTON_COUNT};
+
+    /// XInput is slow to report a missing pad, so a disconnected slot is only retried this often.
+    const RETRY_INTERVAL: Duration = Duration::from_secs(1);
+
+    const BUTTONS: [(XINPUT_GAMEPAD_BUTTON_FLAGS, GamepadButton); BUTTON_COUNT] = [
+        (XINPUT_GAMEPAD_A, GamepadButton::South),
+        (XINPUT_GAMEPAD_B, GamepadButton::East),
+        (XINPUT_GAMEPAD_X, GamepadButton::West),
+        (XINPUT_GAMEPAD_Y, GamepadButton::North),
+        (XINPUT_GAMEPAD_LEFT_SHOULDER, GamepadButton::LeftShoulder),
+        (XINPUT_GAMEPAD_RIGHT_SHOULDER, GamepadButton::RightShoulder),
+        (XINPUT_GAMEPAD_LEFT_THUMB, GamepadButton::LeftStick),
+        (XINPUT_GAMEPAD_RIGHT_THUMB, GamepadButton::RightStick),
+        (XINPUT_GAMEPAD_BACK, GamepadButton::Select),
+        (XINPUT_GAMEPAD_START, GamepadButton::Start),
+        (XINPUT_GAMEPAD_DPAD_UP, GamepadButton::DPadUp),
+        (XINPUT_GAMEPAD_DPAD_DOWN, GamepadButton::DPadDown),
+        (XINPUT_GAMEPAD_DPAD_LEFT, GamepadButton::DPadLeft),
+        (XINPUT_GAMEPAD_DPAD_RIGHT, GamepadButton::DPadRight),
+    ];
+
+    pub(super) struct Source {
+        next_retry: Option<Instant>,
+    }
+
+    impl Source {
+        pub(super) fn new() -> Self {
+            Self { next_retry: None }
+        }
+
+        pub(super) fn read(&mut self) -> PadState {
+            if self.next_retry.is_some_and(|next_retry| Instant::now() < next_retry) {
+                return PadState::default();
+            }
+
+            let mut state = XINPUT_STATE::default();
+            // ERROR_SUCCESS, anything else means no pad in the first slot.
+            if unsafe { XInputGetState(0, &mut state) } != 0 {
+                self.next_retry = Some(Instant::now() + RETRY_INTERVAL);
+                return PadState::default();
+            }
+            self.next_retry = None;
+
+            let gamepad = state.Gamepad;
+            let mut pad = PadState::default();
+            for (flag, button) in BUTTONS {
+                pad.set_button(button, gamepad.wButtons.0 & flag.0 != 0);
+            }
+
+            pad.set_axis(GamepadAxis::LeftStickX, stick(gamepad.sThumbLX));
+            pad.set_axis(GamepadAxis::LeftStickY, stick(gamepad.sThumbLY));
+            pad.set_axis(GamepadAxis::RightStickX, stick(gamepad.sThumbRX));
+            pad.set_axis(GamepadAxis::RightStickY, stick(gamepad.sThumbRY));
+            pad.set_axis(GamepadAxis::LeftTrigger, gamepad.bLeftTrigger as f32 / u8::MAX as f32);
+            pad.set_axis(GamepadAxis::RightTrigger, gamepad.bRightTrigger as f32 / u8::MAX as f32);
+
+            pad
+        }
+    }
+}
+
+#[cfg(target_os = "linux")]
+mod platform {
+    use std::{
+        fs::File, io::Read, thread, time::Duration,
+        sync::{atomic::{AtomicBool, Ordering}, mpsc, Arc},
+    };
+
+    use super::{stick, GamepadAxis, GamepadButton, PadState};
+
+    const DEVICE: &str = "/dev/input/js0";
+    const RETRY_INTERVAL: Duration = Duration::from_secs(1);
+
+    // js_event types, the initial state of every control is flagged with JS_EVENT_INIT.
+    const JS_EVENT_BUTTON: u8 = 0x01;
+    const JS_EVENT_AXIS: u8 = 0x02;
+    const JS_EVENT_INIT: u8 = 0x80;
+
+    /// Engine button of a button number in the xpad driver layout, the guide button has none.
+    fn button(number: u8) -> Option<GamepadButton> {
+        match number {
+            0 => Some(GamepadButton::South),
+            1 => Some(GamepadButton::East),
+            2 => Some(GamepadButton::West),
+            3 => Some(GamepadButton::North),
+            4 => Some(GamepadButton::LeftShoulder),
+            5 => Some(GamepadButton::RightShoulder),
+            6 => Some(GamepadButton::Select),
+            7 => Some(GamepadButton::Start),
+            9 => Some(GamepadButton::LeftStick),
+            10 => Some(GamepadButton::RightStick),
+            _ => None,
+        }
+    }
+
+    enum Message {
+        Event { kind: u8, number: u8, value: i16 },
+        Disconnected,
+    }
+
+    /// Reads the joystick device on its own thread, since std has no non-blocking open.
+    pub(super) struct Source {
+        receiver: mpsc::Receiver<Message>,
+        pad: PadState,
+        /// Set when the source is dropped, the thread returns at its next retry or device event.
+        is_stopped: Arc<AtomicBool>,
+    }
+
+    impl Source {
+        pub(super) fn new() -> Self {
+            let (sender, receiver) = mpsc::channel();
+            let is_stopped = Arc::new(AtomicBool::new(false));
+
+            let thread_stopped = is_stopped.clone();
+            thread::spawn(move || read_device(sender, &thread_stopped));
+
+            Self {
+                receiver,
+                pad: PadState::default(),
+                is_stopped,
+            }
+        }
+
+        pub(super) fn read(&mut self) -> PadState {
+            for message in self.receiver.try_iter() {
+                match message {
+                    Message::Event { kind, number, value } => apply(&mut self.pad, kind & !JS_EVENT_INIT, number, value),
+                    Message::Disconnected => self.pad = PadState::default(),
+                }
+            }
+
+            self.pad
+        }
+    }
+
+    impl Drop for Source {
+        fn drop(&mut self) {
+            // not joined, a read blocks until the pad sends its next event.
+            self.is_stopped.store(true, Ordering::Relaxed);
+        }
+    }
+
+    fn apply(pad: &mut PadState, kind: u8, number: u8, value: i16) {
+        match (kind, number) {
+            (JS_EVENT_BUTTON, number) => {
+                if let Some(button) = button(number) {
+                    pad.set_button(button, value != 0);
+                }
+            }
+            // the device reports down as positive, the engine uses up.
+            (JS_EVENT_AXIS, 0) => pad.set_axis(GamepadAxis::LeftStickX, stick(value)),
+            (JS_EVENT_AXIS, 1) => pad.set_axis(GamepadAxis::LeftStickY, -stick(value)),
+            (JS_EVENT_AXIS, 2) => pad.set_axis(GamepadAxis::LeftTrigger, trigger(value)),
+            (JS_EVENT_AXIS, 3) => pad.set_axis(GamepadAxis::RightStickX, stick(value)),
+            (JS_EVENT_AXIS, 4) => pad.set_axis(GamepadAxis::RightStickY, -stick(value)),
+            (JS_EVENT_AXIS, 5) => pad.set_axis(GamepadAxis::RightTrigger, trigger(value)),
+            // the d-pad is a hat reported as two axes.
+            (JS_EVENT_AXIS, 6) => {
+                pad.set_button(GamepadButton::DPadLeft, value < 0);
+                pad.set_button(GamepadButton::DPadRight, value > 0);
+            }
+            (JS_EVENT_AXIS, 7) => {
+                pad.set_button(GamepadButton::DPadUp, value < 0);
+                pad.set_button(GamepadButton::DPadDown, value > 0);
+            }
+            _ => {}
+        }
+    }
+
+    /// Triggers rest at the negative end of the axis.
+    fn trigger(value: i16) -> f32 {
+        ((value as f32 + i16::MAX as f32) / (2.0 * i16::MAX as f32)).clamp(0.0, 1.0)
+    }
+
+    /// Forward every js_event of the device, reopening it after it is unplugged. Returns when
+    /// `is_stopped` is set or the receiver is gone.
+    fn read_device(sender: mpsc::Sender<Message>, is_stopped: &AtomicBool) {
+        while !is_stopped.load(Ordering::Relaxed) {
+            if let Ok(mut device) = File::open(DEVICE) {
+                // struct js_event { u32 time; i16 value; u8 type; u8 number; }
+                let mut event = [0u8; 8];
+                while device.read_exact(&mut event).is_ok() {
+                    let message = Message::Event {
+                        kind: event[6],
+                        number: event[7],
+                        value: i16::from_ne_bytes([event[4], event[5]]),
+                    };
+                    if sender.send(message).is_err() {
+                        return;
+                    }
+                }
+
+                if sender.send(Message::Disconnected).is_err() {
+                    return;
+                }
+            }
+
+            thread::sleep(RETRY_INTERVAL);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn changes_report_only_what_moved() {
+        let mut previous = PadState::default();
+        previous.set_button(GamepadButton::South, true);
+        previous.set_button(GamepadButton::East, true);
+
+        let mut next = previous;
+        next.set_button(GamepadButton::East, false);
+        next.set_button(GamepadButton::Start, true);
+        next.set_axis(GamepadAxis::RightTrigger, 0.5);
+
+        let mut events = Vec::new();
+        previous.changes(&next, &mut events);
+
+        assert_eq!(events, vec![
+            Event::GamepadUp(GamepadButton::East),
+            Event::GamepadDown(GamepadButton::Start),
+            Event::GamepadAxisMoved(GamepadAxis::RightTrigger, 0.5),
+        ]);
+    }
+
+    #[test]
+    fn stick_deadzone() {
+        assert_eq!(stick(0), 0.0);
+        assert_eq!(stick(i16::MAX / 5), 0.0);
+        assert_eq!(stick(i16::MAX), 1.0);
+        assert_eq!(stick(i16::MIN), -1.0);
+        assert!(stick(i16::MAX / 2) > 0.0);
+    }
+}
@@ -1,15 +1,412 @@
+use cgmath::{Vector2, Zero};
+
+use super::event::{Event, MouseButton};
+
+/// Platform neutral key identifiers, the backends translate their native codes into these.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum KeyCode {
+    A, B, C, D, E, F, G, H, I, J, K, L, M,
+    N, O, P, Q, R, S, T, U, V, W, X, Y, Z,
+
+    Key0, Key1, Key2, Key3, Key4, Key5, Key6, Key7, Key8, Key9,
+
+    F1, F2, F3, F4, F5, F6, F7, F8, F9, F10, F11, F12,
+
+    Escape,
+    Enter,
+    Space,
+    Tab,
+    Backspace,
+    Insert,
+    Delete,
+    Home,
+    End,
+    PageUp,
+    PageDown,
+
+    Left,
+    Right,
+    Up,
+    Down,
+
+    LeftShift,
+    RightShift,
+    LeftControl,
+    RightControl,
+    LeftAlt,
+    RightAlt,
+
+    Unknown,
+}
+
+const KEY_COUNT: usize = KeyCode::Unknown as usize + 1;
+
+const LETTERS: [KeyCode; 26] = [
+    KeyCode::A, KeyCode::B, KeyCode::C, KeyCode::D, KeyCode::E, KeyCode::F, KeyCode::G,
+    KeyCode::H, KeyCode::I, KeyCode::J, KeyCode::K, KeyCode::L, KeyCode::M, KeyCode::N,
+    KeyCode::O, KeyCode::P, KeyCode::Q, KeyCode::R, KeyCode::S, KeyCode::T, KeyCode::U,
+    KeyCode::V, KeyCode::W, KeyCode::X, KeyCode::Y, KeyCode::Z,
+];
+
+const DIGITS: [KeyCode; 10] = [
+    KeyCode::Key0, KeyCode::Key1, KeyCode::Key2, KeyCode::Key3, KeyCode::Key4,
+    KeyCode::Key5, KeyCode::Key6, KeyCode::Key7, KeyCode::Key8, KeyCode::Key9,
+];
+
+const FUNCTION_KEYS: [KeyCode; 12] = [
+    KeyCode::F1, KeyCode::F2, KeyCode::F3, KeyCode::F4, KeyCode::F5, KeyCode::F6,
+    KeyCode::F7, KeyCode::F8, KeyCode::F9, KeyCode::F10, KeyCode::F11, KeyCode::F12,
+];
+
+const NAMED_KEYS: [KeyCode; 22] = [
+    KeyCode::Escape, KeyCode::Enter, KeyCode::Space, KeyCode::Tab, KeyCode::Backspace,
+    KeyCode::Insert, KeyCode::Delete, KeyCode::Home, KeyCode::End, KeyCode::PageUp, KeyCode::PageDown,
+    KeyCode::Left, KeyCode::Right, KeyCode::Up, KeyCode::Down,
+    KeyCode::LeftShift, KeyCode::RightShift, KeyCode::LeftControl, KeyCode::RightControl,
+    KeyCode::LeftAlt, KeyCode::RightAlt, KeyCode::Unknown,
+];
+
+impl KeyCode {
+    /// Letter key from its position in the alphabet, starting at 0 for A.
+    pub fn letter(index: u32) -> Self {
+        LETTERS.get(index as usize).copied().unwrap_or(Self::Unknown)
+    }
+
+    pub fn digit(index: u32) -> Self {
+        DIGITS.get(index as usize).copied().unwrap_or(Self::Unknown)
+    }
+
+    /// Function key from its index, starting at 0 for F1.
+    pub fn function(index: u32) -> Self {
+        FUNCTION_KEYS.get(index as usize).copied().unwrap_or(Self::Unknown)
+    }
+
+    /// Key from the name it is written with in binding files, the same as its variant name.
+    pub fn from_name(name: &str) -> Option<Self> {
+        LETTERS.iter()
+            .chain(DIGITS.iter())
+            .chain(FUNCTION_KEYS.iter())
+            .chain(NAMED_KEYS.iter())
+            .find(|key| format!("{key:?}") == name)
+            .copied()
+    }
+
+    /// Inverse of `key as usize`.
+    pub(crate) fn from_index(index: usize) -> Option<Self> {
+        LETTERS.iter()
+            .chain(DIGITS.iter())
+            .chain(FUNCTION_KEYS.iter())
+            .chain(NAMED_KEYS.iter())
+            .nth(index)
+            .copied()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum GamepadButton {
+    /// Bottom face button, A on Xbox layouts.
+    South,
+    East,
+    West,
+    North,
+    LeftShoulder,
+    RightShoulder,
+    LeftStick,
+    RightStick,
+    Select,
+    Start,
+    DPadUp,
+    DPadDown,
+    DPadLeft,
+    DPadRight,
+}
+
+pub(crate) const GAMEPAD_BUTTONS: [GamepadButton; 14] = [
+    GamepadButton::South, GamepadButton::East, GamepadButton::West, GamepadButton::North,
+    GamepadButton::LeftShoulder, GamepadButton::RightShoulder, GamepadButton::LeftStick, GamepadButton::RightStick,
+    GamepadButton::Select, GamepadButton::Start,
+    GamepadButton::DPadUp, GamepadButton::DPadDown, GamepadButton::DPadLeft, GamepadButton::DPadRight,
+];
+
+impl GamepadButton {
+    pub fn from_name(name: &str) -> Option<Self> {
+        GAMEPAD_BUTTONS.iter().find(|button| format!("{button:?}") == name).copied()
+    }
+
+    pub(crate) fn from_index(index: usize) -> Option<Self> {
+        GAMEPAD_BUTTONS.get(index).copied()
+    }
+}
+
+/// Analog gamepad inputs, sticks in -1..1 with up and right positive, triggers in 0..1.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum GamepadAxis {
+    LeftStickX,
+    LeftStickY,
+    RightStickX,
+    RightStickY,
+    LeftTrigger,
+    RightTrigger,
+}
+
+pub(crate) const GAMEPAD_AXES: [GamepadAxis; 6] = [
+    GamepadAxis::LeftStickX, GamepadAxis::LeftStickY, 
+    GamepadAxis::RightStickX, GamepadAxis::RightStickY, 
+    GamepadAxis::LeftTrigger, GamepadAxis::RightTrigger,
+];
+
+impl GamepadAxis {
+    pub fn from_name(name: &str) -> Option<Self> {
+        GAMEPAD_AXES.iter().find(|axis| format!("{axis:?}") == name).copied()
+    }
+
+    pub(crate) fn from_index(index: usize) -> Option<Self> {
+        GAMEPAD_AXES.get(index).copied()
+    }
+}
+
+const BUTTON_COUNT: usize = 8;
+
+fn button_index(button: MouseButton) -> usize {
+    match button {
+        MouseButton::Left => 0,
+        MouseButton::Right => 1,
+        MouseButton::Middle => 2,
+        MouseButton::Other(extra) => (2 + extra as usize).min(BUTTON_COUNT - 1),
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Modifiers {
+    pub shift: bool,
+    pub control: bool,
+    pub alt: bool,
+}
+
 pub struct InputManager {
-    pub(crate) input: u8
+    keys_down: [bool; KEY_COUNT],
+    keys_pressed: [bool; KEY_COUNT],
+    keys_released: [bool; KEY_COUNT],
+
+    buttons_down: [bool; BUTTON_COUNT],
+    buttons_pressed: [bool; BUTTON_COUNT],
+    buttons_released: [bool; BUTTON_COUNT],
+
+    cursor_position: Vector2<f32>,
+    cursor_delta: Vector2<f32>,
+    scroll_delta: f32,
+
+    gamepad_down: [bool; GAMEPAD_BUTTONS.len()],
+    gamepad_pressed: [bool; GAMEPAD_BUTTONS.len()],
+    gamepad_released: [bool; GAMEPAD_BUTTONS.len()],
+    gamepad_axes: [f32; GAMEPAD_AXES.len()],
 }
 
 impl InputManager {
     pub fn new() -> Self {
-        Self { 
-            input: u8::default()
+        Self {
+            keys_down: [false; KEY_COUNT],
+            keys_pressed: [false; KEY_COUNT],
+            keys_released: [false; KEY_COUNT],
+
+            buttons_down: [false; BUTTON_COUNT],
+            buttons_pressed: [false; BUTTON_COUNT],
+            buttons_released: [false; BUTTON_COUNT],
+
+            cursor_position: Vector2::zero(),
+            cursor_delta: Vector2::zero(),
+            scroll_delta: 0.0,
+
+            gamepad_down: [false; GAMEPAD_BUTTONS.len()],
+            gamepad_pressed: [false; GAMEPAD_BUTTONS.len()],
+            gamepad_released: [false; GAMEPAD_BUTTONS.len()],
+            gamepad_axes: [0.0; GAMEPAD_AXES.len()],
+        }
+    }
+
+    /// Forget the edges of the previous frame, call it before feeding the frame events.
+    pub(crate) fn begin_frame(&mut self) {
+        self.keys_pressed = [false; KEY_COUNT];
+        self.keys_released = [false; KEY_COUNT];
+
+        self.buttons_pressed = [false; BUTTON_COUNT];
+        self.buttons_released = [false; BUTTON_COUNT];
+
+        self.cursor_delta = Vector2::zero();
+        self.scroll_delta = 0.0;
+
+        self.gamepad_pressed = [false; GAMEPAD_BUTTONS.len()];
+        self.gamepad_released = [false; GAMEPAD_BUTTONS.len()];
+    }
+
+    /// Apply an input event, anything else is ignored.
+    pub(crate) fn handle_event(&mut self, event: Event) {
+        match event {
+            Event::Focused(false) => self.release_all(),
+            Event::KeyDown(key) => self.key_down(key),
+            Event::KeyUp(key) => self.key_up(key),
+            Event::MouseMoved(position) => self.mouse_moved(position),
+            Event::MouseMotion(delta) => self.mouse_motion(delta),
+            Event::MouseDown(button) => self.mouse_down(button),
+            Event::MouseUp(button) => self.mouse_up(button),
+            Event::MouseWheel(delta) => self.mouse_wheel(delta),
+            Event::GamepadDown(button) => self.gamepad_down(button),
+            Event::GamepadUp(button) => self.gamepad_up(button),
+            Event::GamepadAxisMoved(axis, value) => self.gamepad_axis_moved(axis, value),
+            _ => ()
+        }
+    }
+
+    pub(crate) fn key_down(&mut self, key: KeyCode) {
+        // auto repeat sends more key downs while the key is held, those are not new presses.
+        if !self.keys_down[key as usize] {
+            self.keys_pressed[key as usize] = true;
+        }
+        self.keys_down[key as usize] = true;
+    }
+
+    pub(crate) fn key_up(&mut self, key: KeyCode) {
+        if self.keys_down[key as usize] {
+            self.keys_released[key as usize] = true;
+        }
+        self.keys_down[key as usize] = false;
+    }
+
+    pub(crate) fn mouse_down(&mut self, button: MouseButton) {
+        let index = button_index(button);
+
+        if !self.buttons_down[index] {
+            self.buttons_pressed[index] = true;
+        }
+        self.buttons_down[index] = true;
+    }
+
+    pub(crate) fn mouse_up(&mut self, button: MouseButton) {
+        let index = button_index(button);
+
+        if self.buttons_down[index] {
+            self.buttons_released[index] = true;
+        }
+        self.buttons_down[index] = false;
+    }
+
+    pub(crate) fn mouse_moved(&mut self, position: Vector2<i32>) {
+        self.cursor_position = position.cast().unwrap();
+    }
+
+    pub(crate) fn mouse_motion(&mut self, delta: Vector2<f32>) {
+        self.cursor_delta += delta;
+    }
+
+    pub(crate) fn mouse_wheel(&mut self, delta: f32) {
+        self.scroll_delta += delta;
+    }
+
+    pub(crate) fn gamepad_down(&mut self, button: GamepadButton) {
+        if !self.gamepad_down[button as usize] {
+            self.gamepad_pressed[button as usize] = true;
+        }
+        self.gamepad_down[button as usize] = true;
+    }
+
+    pub(crate) fn gamepad_up(&mut self, button: GamepadButton) {
+        if self.gamepad_down[button as usize] {
+            self.gamepad_released[button as usize] = true;
+        }
+        self.gamepad_down[button as usize] = false;
+    }
+
+    pub(crate) fn gamepad_axis_moved(&mut self, axis: GamepadAxis, value: f32) {
+        self.gamepad_axes[axis as usize] = value;
+    }
+
+    /// Release every held key and button, used when the window loses focus and stops receiving ups.
+    pub(crate) fn release_all(&mut self) {
+        for (released, down) in self.keys_released.iter_mut().zip(self.keys_down.iter_mut()) {
+            *released |= *down;
+            *down = false;
+        }
+
+        for (released, down) in self.buttons_released.iter_mut().zip(self.buttons_down.iter_mut()) {
+            *released |= *down;
+            *down = false;
+        }
+
+        for (released, down) in self.gamepad_released.iter_mut().zip(self.gamepad_down.iter_mut()) {
+            *released |= *down;
+            *down = false;
+        }
+        self.gamepad_axes = [0.0; GAMEPAD_AXES.len()];
+    }
+
+    pub fn is_down(&self, key: KeyCode) -> bool {
+        self.keys_down[key as usize]
+    }
+
+    /// True only on the frame the key went down.
+    pub fn just_pressed(&self, key: KeyCode) -> bool {
+        self.keys_pressed[key as usize]
+    }
+
+    /// True only on the frame the key went up.
+    pub fn just_released(&self, key: KeyCode) -> bool {
+        self.keys_released[key as usize]
+    }
+
+    pub fn is_mouse_down(&self, button: MouseButton) -> bool {
+        self.buttons_down[button_index(button)]
+    }
+
+    pub fn mouse_just_pressed(&self, button: MouseButton) -> bool {
+        self.buttons_pressed[button_index(button)]
+    }
+
+    pub fn mouse_just_released(&self, button: MouseButton) -> bool {
+        self.buttons_released[button_index(button)]
+    }
+
+    /// Cursor position in window pixels, from the top left corner.
+    pub fn cursor_position(&self) -> Vector2<f32> {
+        self.cursor_position
+    }
+
+    /// Pointer motion accumulated over the frame, also valid while the cursor is locked.
+    pub fn cursor_delta(&self) -> Vector2<f32> {
+        self.cursor_delta
+    }
+
+    /// Wheel notches scrolled during the frame, positive away from the user.
+    pub fn scroll_delta(&self) -> f32 {
+        self.scroll_delta
+    }
+
+    pub fn is_gamepad_down(&self, button: GamepadButton) -> bool {
+        self.gamepad_down[button as usize]
+    }
+
+    pub fn gamepad_just_pressed(&self, button: GamepadButton) -> bool {
+        self.gamepad_pressed[button as usize]
+    }
+
+    pub fn gamepad_just_released(&self, button: GamepadButton) -> bool {
+        self.gamepad_released[button as usize]
+    }
+
+    pub fn gamepad_axis(&self, axis: GamepadAxis) -> f32 {
+        self.gamepad_axes[axis as usize]
+    }
+
+    pub fn modifiers(&self) -> Modifiers {
+        Modifiers {
+            shift: self.is_down(KeyCode::LeftShift) || self.is_down(KeyCode::RightShift),
+            control: self.is_down(KeyCode::LeftControl) || self.is_down(KeyCode::RightControl),
+            alt: self.is_down(KeyCode::LeftAlt) || self.is_down(KeyCode::RightAlt),
         }
     }
+}
 
-    pub(crate) fn register(&mut self, input: u8) {
-        self.input = input
+impl Default for InputManager {
+    fn default() -> Self {
+        Self::new()
     }
-}
\ No newline at end of file
+}
@@ -1,6 +1,20 @@
 pub(crate) mod device;
 pub(crate) mod surface;
-pub(crate) mod time;
-pub(crate) mod entity;
-pub(crate) mod camera;
-pub(crate) mod input;
\ No newline at end of file
+pub mod window;
+pub mod event;
+#[cfg(windows)]
+pub(crate) mod win32;
+#[cfg(target_os = "linux")]
+pub(crate) mod xlib;
+pub(crate) mod gamepad;
+pub mod time;
+pub mod tween;
+pub mod entity;
+pub mod ecs;
+pub mod scene;
+pub mod prefab;
+pub mod camera;
+pub mod bounds;
+pub mod input;
+pub mod action;
+pub mod replay;
\ No newline at end of file
@@ -0,0 +1,405 @@
+use std::{collections::HashMap, fs, io, path::{Path, PathBuf}, time::SystemTime};
+
+use super::{entity::{Entity, EntityId, EntityJoin, Light, MaterialRef, MeshRef, Transform}, scene::{Scene, SceneEntity}};
+
+/// Entities kept in their own file to be placed many times in scenes.
+///
+/// Prefab files use the scene format without cameras or prefabs of their own. The first
+/// entity is the root an instance places, the others are its children.
+#[derive(Debug, Clone)]
+pub struct Prefab {
+    pub name: String,
+    pub path: PathBuf,
+    /// Meshes, textures and entities of the file, empty until it is loaded.
+    pub scene: Scene,
+    /// Modification time of the file when it was last loaded.
+    modified: Option<SystemTime>,
+}
+
+impl Prefab {
+    /// Prefab stored at `path`, not loaded yet.
+    pub fn new(name: &str, path: &Path) -> Self {
+        Self {
+            name: name.to_owned(),
+            path: path.to_owned(),
+            scene: Scene::new(),
+            modified: None,
+        }
+    }
+
+    /// Prefab of `text`, as if read from `path`.
+    pub fn parse(name: &str, path: &Path, text: &str) -> io::Result<Self> {
+        let mut prefab = Self::new(name, path);
+        prefab.set_text(text)?;
+
+        Ok(prefab)
+    }
+
+    /// Read the file again, a file that failed to load is retried once it changes.
+    pub fn load(&mut self) -> io::Result<()> {
+        self.modified = Self::modified(&self.path);
+
+        self.set_text(&fs::read_to_string(&self.path)?)
+    }
+
+    fn set_text(&mut self, text: &str) -> io::Result<()> {
+        let invalid = |reason: &str| io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("{}: {reason}", self.path.display())
+        );
+
+        let scene = Scene::parse(text).map_err(|error| invalid(&error.to_string()))?;
+        if scene.entities.is_empty() {
+            return Err(invalid("a prefab needs an entity"));
+        }
+        if !scene.cameras.is_empty() {
+            return Err(invalid("prefabs hold no cameras"));
+        }
+        if !scene.prefabs.is_empty() {
+            return Err(invalid("prefabs can not place other prefabs"));
+        }
+
+        self.scene = scene;
+
+        Ok(())
+    }
+
+    fn modified(path: &Path) -> Option<SystemTime> {
+        fs::metadata(path).and_then(|metadata| metadata.modified()).ok()
+    }
+
+    /// Whether the file changed since it was loaded.
+    pub fn is_changed(&self) -> bool {
+        Self::modified(&self.path) != self.modified
+    }
+
+    /// Entity an instance is placed as, `None` until loaded.
+    pub fn root(&self) -> Option<&SceneEntity> {
+        self.scene.entities.first()
+    }
+
+    /// Add an instance described by `instance`, whose fields override the prefab root,
+    /// with the prefab children under it. Assets are looked up in `assets`.
+    pub fn instantiate(&self, entities: &mut EntityJoin, instance: &SceneEntity, parent: Option<EntityId>, assets: &Scene) -> EntityId {
+        let fields = match self.root() {
+            Some(root) => instance.or(root),
+            None => instance.clone(),
+        };
+
+        let entity = assets.entity(&fields).with(PrefabInstance {
+            prefab: self.name.clone(),
+            overrides: Overrides::of(instance),
+        });
+        let id = match parent {
+            Some(parent) => entities.add_child(parent, entity),
+            None => entities.add(entity),
+        };
+
+        self.update_parts(entities, id, assets);
+
+        id
+    }
+
+    /// Bring every instance of the prefab up to date after it was loaded again. Fields an
+    /// instance overrides are left alone, tags stay as they were spawned.
+    pub fn update_instances(&self, entities: &mut EntityJoin, assets: &Scene) {
+        let instances: Vec<(EntityId, Overrides)> = entities.query::<&PrefabInstance>()
+            .filter(|(_, instance)| instance.prefab == self.name)
+            .map(|(id, instance)| (id, instance.overrides))
+            .collect();
+
+        for (id, overrides) in instances {
+            if let Some(root) = self.root() {
+                Self::apply(entities, id, root, overrides, assets);
+            }
+            self.update_parts(entities, id, assets);
+        }
+    }
+
+    /// Match the children of `instance` with the prefab children, adding, moving and
+    /// removing them as needed.
+    fn update_parts(&self, entities: &mut EntityJoin, instance: EntityId, assets: &Scene) {
+        let instance_name = entities.get(instance).and_then(Entity::name).unwrap_or_default().to_owned();
+        let root_name = self.root().map(|root| root.name.as_str());
+        let children = self.scene.entities.get(1..).unwrap_or_default();
+
+        let mut parts = Self::parts(entities, instance);
+
+        for child in children {
+            let parent = match child.parent.as_deref() {
+                Some(parent) if Some(parent) != root_name => parts.get(parent).copied().unwrap_or(instance),
+                _ => instance,
+            };
+
+            match parts.get(&child.name) {
+                Some(&part) => {
+                    if entities.parent(part) != Some(parent) {
+                        entities.reparent(part, parent);
+                    }
+                    Self::apply(entities, part, child, Overrides::default(), assets);
+                }
+                None => {
+                    let entity = assets.entity(child)
+                        .with_name(&format!("{instance_name}.{}", child.name))
+                        .with(PrefabPart { source: child.name.clone() });
+                    parts.insert(child.name.clone(), entities.add_child(parent, entity));
+                }
+            }
+        }
+
+        let stale: Vec<EntityId> = parts.into_iter()
+            .filter(|(source, _)| !children.iter().any(|child| child.name == *source))
+            .map(|(_, part)| part)
+            .collect();
+
+        // entities placed under a removed part are not the prefab's to remove, they keep their
+        // local transform under the instance.
+        for &part in &stale {
+            let placed: Vec<EntityId> = entities.children(part)
+                .filter(|&child| entities.component::<PrefabPart>(child).is_none())
+                .collect();
+            for child in placed {
+                entities.reparent(child, instance);
+            }
+        }
+
+        for part in stale {
+            entities.remove(part);
+        }
+    }
+
+    /// Entities spawned for the prefab children under `instance`, by their name in the prefab.
+    pub fn parts(entities: &EntityJoin, instance: EntityId) -> HashMap<String, EntityId> {
+        let mut parts = HashMap::new();
+
+        // parts only hang off the instance and other parts.
+        let mut stack = vec![instance];
+        while let Some(id) = stack.pop() {
+            for child in entities.children(id) {
+                if let Some(part) = entities.component::<PrefabPart>(child) {
+                    parts.insert(part.source.clone(), child);
+                    stack.push(child);
+                }
+            }
+        }
+
+        parts
+    }
+
+    /// Set the transform and components of `id` from `fields`, except the overridden ones.
+    fn apply(entities: &mut EntityJoin, id: EntityId, fields: &SceneEntity, overrides: Overrides, assets: &Scene) {
+        let transform = fields.transform();
+        if let Some(current) = entities.component_mut::<Transform>(id) {
+            if !overrides.position {
+                current.position = transform.position;
+            }
+            if !overrides.rotation {
+                current.rotation = transform.rotation;
+            }
+            if !overrides.scale {
+                current.scale = transform.scale;
+            }
+        }
+
+        if !overrides.mesh {
+            match fields.mesh.as_deref().and_then(|mesh| assets.mesh_index(mesh)) {
+                Some(mesh) => {
+                    entities.insert(id, MeshRef(mesh));
+                }
+                None => {
+                    entities.remove_component::<MeshRef>(id);
+                }
+            }
+        }
+        if !overrides.texture {
+            match fields.texture.as_deref().and_then(|texture| assets.texture_index(texture)) {
+                Some(texture) => {
+                    entities.insert(id, MaterialRef(texture));
+                }
+                None => {
+                    entities.remove_component::<MaterialRef>(id);
+                }
+            }
+        }
+        if !overrides.light {
+            match fields.light {
+                Some(light) => {
+                    entities.insert(id, light);
+                }
+                None => {
+                    entities.remove_component::<Light>(id);
+                }
+            }
+        }
+    }
+}
+
+/// Fields a prefab instance sets itself instead of taking them from the prefab.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Overrides {
+    pub position: bool,
+    pub rotation: bool,
+    pub scale: bool,
+    pub mesh: bool,
+    pub texture: bool,
+    pub light: bool,
+}
+
+impl Overrides {
+    /// Fields `instance` gives a value.
+    pub fn of(instance: &SceneEntity) -> Self {
+        Self {
+            position: instance.position.is_some(),
+            rotation: instance.rotation.is_some(),
+            scale: instance.scale.is_some(),
+            mesh: instance.mesh.is_some(),
+            texture: instance.texture.is_some(),
+            light: instance.light.is_some(),
+        }
+    }
+}
+
+/// Root entity of a prefab instance.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PrefabInstance {
+    pub prefab: String,
+    pub overrides: Overrides,
+}
+
+/// Entity spawned for a child of a prefab, `source` is its name in the prefab.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PrefabPart {
+    pub source: String,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cgmath::Vector3;
+
+    const SCENE: &str = "\
+mesh segment = res/Segment.obj
+mesh post = res/Post.obj
+texture wood = res/Wood.png
+texture rust = res/Rust.png
+prefab segment = res/Segment.prefab
+entity first = prefab segment
+entity second = prefab segment, position 4 0 0, texture rust
+entity flag = parent second.post, position 0 1 0
+";
+
+    const SEGMENT: &str = "\
+mesh segment = res/Segment.obj
+mesh post = res/Post.obj
+texture wood = res/Wood.png
+entity segment = mesh segment, texture wood, tag track
+entity post = parent segment, position 1 0 0, mesh post, texture wood
+";
+
+    fn scene() -> Scene {
+        let mut scene = Scene::parse(SCENE).unwrap();
+        scene.prefabs[0] = Prefab::parse("segment", Path::new("res/Segment.prefab"), SEGMENT).unwrap();
+        scene
+    }
+
+    #[test]
+    fn instances_override_the_prefab_root() {
+        let scene = scene();
+
+        let mut entities = EntityJoin::new();
+        let ids = scene.spawn(&mut entities);
+
+        assert_eq!(entities.component::<MaterialRef>(ids[0]), Some(&MaterialRef(0)));
+        assert_eq!(entities.component::<MaterialRef>(ids[1]), Some(&MaterialRef(1)));
+        assert_eq!(entities.component::<Transform>(ids[1]).unwrap().position, Vector3::new(4.0, 0.0, 0.0));
+        assert!(entities.get(ids[1]).unwrap().has_tag("track"));
+
+        let post = entities.find("second.post").unwrap();
+        assert_eq!(entities.parent(post), Some(ids[1]));
+        assert_eq!(entities.parent(ids[2]), Some(post));
+        assert_eq!(entities.component::<MeshRef>(post), Some(&MeshRef(1)));
+    }
+
+    #[test]
+    fn prefab_edits_skip_overridden_fields() {
+        let mut scene = scene();
+
+        let mut entities = EntityJoin::new();
+        let ids = scene.spawn(&mut entities);
+        let post = entities.find("first.post").unwrap();
+
+        let edited = "\
+mesh segment = res/Segment.obj
+mesh post = res/Post.obj
+texture wood = res/Wood.png
+texture rust = res/Rust.png
+entity segment = position 0 0 2, mesh segment, texture rust
+entity post = parent segment, position -1 0 0, mesh post, texture wood
+entity lamp = parent post, position 0 2 0, mesh post
+";
+        scene.prefabs[0] = Prefab::parse("segment", Path::new("res/Segment.prefab"), edited).unwrap();
+        scene.prefabs[0].update_instances(&mut entities, &scene);
+
+        assert_eq!(entities.component::<Transform>(ids[0]).unwrap().position, Vector3::new(0.0, 0.0, 2.0));
+        assert_eq!(entities.component::<Transform>(ids[1]).unwrap().position, Vector3::new(4.0, 0.0, 0.0));
+        assert_eq!(entities.component::<MaterialRef>(ids[0]), Some(&MaterialRef(1)));
+
+        assert_eq!(entities.find("first.post"), Some(post));
+        assert_eq!(entities.component::<Transform>(post).unwrap().position, Vector3::new(-1.0, 0.0, 0.0));
+        assert_eq!(entities.parent(entities.find("first.lamp").unwrap()), Some(post));
+
+        let edited = "mesh segment = res/Segment.obj\nentity segment = mesh segment\n";
+        scene.prefabs[0] = Prefab::parse("segment", Path::new("res/Segment.prefab"), edited).unwrap();
+        scene.prefabs[0].update_instances(&mut entities, &scene);
+
+        assert!(!entities.contains(post));
+        assert!(entities.find("first.lamp").is_none());
+        assert_eq!(entities.component::<MaterialRef>(ids[0]), None);
+        assert_eq!(entities.component::<MaterialRef>(ids[1]), Some(&MaterialRef(1)));
+    }
+
+    #[test]
+    fn removed_parts_leave_placed_entities_to_the_instance() {
+        let mut scene = scene();
+
+        let mut entities = EntityJoin::new();
+        let ids = scene.spawn(&mut entities);
+
+        let edited = "mesh segment = res/Segment.obj\nentity segment = mesh segment\n";
+        scene.prefabs[0] = Prefab::parse("segment", Path::new("res/Segment.prefab"), edited).unwrap();
+        scene.prefabs[0].update_instances(&mut entities, &scene);
+
+        assert!(entities.find("second.post").is_none());
+        assert_eq!(entities.find("flag"), Some(ids[2]));
+        assert_eq!(entities.parent(ids[2]), Some(ids[1]));
+        assert_eq!(entities.component::<Transform>(ids[2]).unwrap().position, Vector3::new(0.0, 1.0, 0.0));
+    }
+
+    #[test]
+    fn saved_instances_keep_only_their_overrides() {
+        let scene = scene();
+
+        let mut entities = EntityJoin::new();
+        let ids = scene.spawn(&mut entities);
+        entities.component_mut::<Transform>(ids[0]).unwrap().position = Vector3::new(-4.0, 0.0, 0.0);
+
+        let saved = Scene::from_entities(&entities, scene.clone());
+
+        let names: Vec<&str> = saved.entities.iter().map(|entity| entity.name.as_str()).collect();
+        assert_eq!(names, ["first", "second", "flag"]);
+
+        let mut moved = scene.entities[0].clone();
+        moved.position = Some(Vector3::new(-4.0, 0.0, 0.0));
+        assert_eq!(saved.entities[0], moved);
+        assert_eq!(saved.entities[1..], scene.entities[1..]);
+    }
+
+    #[test]
+    fn prefabs_refuse_cameras_and_nesting() {
+        let path = Path::new("res/Bad.prefab");
+
+        assert!(Prefab::parse("bad", path, "").is_err());
+        assert!(Prefab::parse("bad", path, "camera main = position 0 0 0\nentity a =\n").is_err());
+        assert!(Prefab::parse("bad", path, "prefab other = res/Other.prefab\nentity a = prefab other\n").is_err());
+    }
+}
@@ -0,0 +1,352 @@
+use std::{fs, io, path::Path};
+
+use cgmath::Vector2;
+
+use super::{event::{Event, MouseButton}, input::{GamepadAxis, GamepadButton, KeyCode}};
+
+const MAGIC: &[u8; 4] = b"RINP";
+const VERSION: u8 = 2;
+
+/// Input events of one frame and the delta time the frame was simulated with.
+#[derive(Debug, Clone, PartialEq)]
+pub struct InputFrame {
+    pub delta_time: f32,
+    pub events: Vec<Event>,
+}
+
+/// Per-frame input stream of a session, replaying it feeds `InputManager` the same state frame by frame.
+///
+/// Only the events that reach `InputManager` are kept, window events of the playback window still
+/// come from the platform.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct InputRecording {
+    frames: Vec<InputFrame>,
+}
+
+impl InputRecording {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn push_frame(&mut self, delta_time: f32, events: &[Event]) {
+        let events = events.iter().copied().filter(is_input).collect();
+
+        self.frames.push(InputFrame { delta_time, events });
+    }
+
+    pub fn frames(&self) -> &[InputFrame] {
+        &self.frames
+    }
+
+    pub fn load(path: &Path) -> io::Result<Self> {
+        Self::decode(&fs::read(path)?)
+    }
+
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        fs::write(path, self.encode())
+    }
+
+    pub fn encode(&self) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        bytes.extend_from_slice(MAGIC);
+        bytes.push(VERSION);
+        bytes.extend_from_slice(&(self.frames.len() as u32).to_le_bytes());
+
+        for frame in &self.frames {
+            bytes.extend_from_slice(&frame.delta_time.to_le_bytes());
+            bytes.extend_from_slice(&(frame.events.len() as u32).to_le_bytes());
+
+            for event in &frame.events {
+                encode_event(event, &mut bytes);
+            }
+        }
+
+        bytes
+    }
+
+    pub fn decode(bytes: &[u8]) -> io::Result<Self> {
+        let mut reader = Reader { bytes };
+
+        if reader.take(MAGIC.len())? != MAGIC {
+            return Err(invalid("not an input recording"));
+        }
+        if reader.u8()? != VERSION {
+            return Err(invalid("unsupported input recording version"));
+        }
+
+        // a frame takes at least 8 bytes, a corrupt count must not reserve more than the file holds.
+        let frame_count = reader.u32()?;
+        let mut frames = Vec::with_capacity((frame_count as usize).min(reader.bytes.len() / 8));
+        for _ in 0..frame_count {
+            let delta_time = reader.f32()?;
+            let event_count = reader.u32()?;
+
+            let events = (0..event_count)
+                .map(|_| decode_event(&mut reader))
+                .collect::<io::Result<Vec<_>>>()?;
+
+            frames.push(InputFrame { delta_time, events });
+        }
+
+        Ok(Self { frames })
+    }
+}
+
+/// Plays a recording back one frame at a time.
+pub struct InputPlayback {
+    recording: InputRecording,
+    next_frame: usize,
+}
+
+impl InputPlayback {
+    pub fn new(recording: InputRecording) -> Self {
+        Self {
+            recording,
+            next_frame: 0,
+        }
+    }
+
+    /// The next recorded frame, `None` once the recording is over.
+    pub fn next_frame(&mut self) -> Option<&InputFrame> {
+        let frame = self.recording.frames.get(self.next_frame)?;
+        self.next_frame += 1;
+
+        Some(frame)
+    }
+}
+
+/// Events that change `InputManager`, losing the focus releases every input.
+pub fn is_input(event: &Event) -> bool {
+    matches!(
+        event,
+        Event::KeyDown(_) | Event::KeyUp(_)
+            | Event::MouseMoved(_) | Event::MouseMotion(_) | Event::MouseDown(_) | Event::MouseUp(_) | Event::MouseWheel(_)
+            | Event::GamepadDown(_) | Event::GamepadUp(_) | Event::GamepadAxisMoved(..)
+            | Event::Focused(_)
+    )
+}
+
+fn invalid(reason: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, reason)
+}
+
+fn encode_button(button: MouseButton) -> u8 {
+    match button {
+        MouseButton::Left => 0,
+        MouseButton::Right => 1,
+        MouseButton::Middle => 2,
+        MouseButton::Other(extra) => extra.saturating_add(3),
+    }
+}
+
+fn decode_button(byte: u8) -> MouseButton {
+    match byte {
+        0 => MouseButton::Left,
+        1 => MouseButton::Right,
+        2 => MouseButton::Middle,
+        extra => MouseButton::Other(extra - 3),
+    }
+}
+
+fn encode_event(event: &Event, bytes: &mut Vec<u8>) {
+    match *event {
+        Event::KeyDown(key) => bytes.extend_from_slice(&[0, key as u8]),
+        Event::KeyUp(key) => bytes.extend_from_slice(&[1, key as u8]),
+        Event::MouseMoved(position) => {
+            bytes.push(2);
+            bytes.extend_from_slice(&position.x.to_le_bytes());
+            bytes.extend_from_slice(&position.y.to_le_bytes());
+        }
+        Event::MouseMotion(delta) => {
+            bytes.push(3);
+            bytes.extend_from_slice(&delta.x.to_le_bytes());
+            bytes.extend_from_slice(&delta.y.to_le_bytes());
+        }
+        Event::MouseDown(button) => bytes.extend_from_slice(&[4, encode_button(button)]),
+        Event::MouseUp(button) => bytes.extend_from_slice(&[5, encode_button(button)]),
+        Event::MouseWheel(delta) => {
+            bytes.push(6);
+            bytes.extend_from_slice(&delta.to_le_bytes());
+        }
+        Event::GamepadDown(button) => bytes.extend_from_slice(&[7, button as u8]),
+        Event::GamepadUp(button) => bytes.extend_from_slice(&[8, button as u8]),
+        Event::GamepadAxisMoved(axis, value) => {
+            bytes.extend_from_slice(&[9, axis as u8]);
+            bytes.extend_from_slice(&value.to_le_bytes());
+        }
+        Event::Focused(focused) => bytes.extend_from_slice(&[10, focused as u8]),
+        _ => unreachable!("{event:?} is not an input event"),
+    }
+}
+
+fn decode_event(reader: &mut Reader) -> io::Result<Event> {
+    let key = |index: u8| KeyCode::from_index(index as usize).ok_or_else(|| invalid("unknown key"));
+    let gamepad_button = |index: u8| GamepadButton::from_index(index as usize).ok_or_else(|| invalid("unknown gamepad button"));
+
+    let event = match reader.u8()? {
+        0 => Event::KeyDown(key(reader.u8()?)?),
+        1 => Event::KeyUp(key(reader.u8()?)?),
+        2 => Event::MouseMoved(Vector2 { x: reader.i32()?, y: reader.i32()? }),
+        3 => Event::MouseMotion(Vector2 { x: reader.f32()?, y: reader.f32()? }),
+        4 => Event::MouseDown(decode_button(reader.u8()?)),
+        5 => Event::MouseUp(decode_button(reader.u8()?)),
+        6 => Event::MouseWheel(reader.f32()?),
+        7 => Event::GamepadDown(gamepad_button(reader.u8()?)?),
+        8 => Event::GamepadUp(gamepad_button(reader.u8()?)?),
+        9 => {
+            let axis = GamepadAxis::from_index(reader.u8()? as usize).ok_or_else(|| invalid("unknown gamepad axis"))?;
+            Event::GamepadAxisMoved(axis, reader.f32()?)
+        }
+        10 => Event::Focused(reader.u8()? != 0),
+        _ => return Err(invalid("unknown event")),
+    };
+
+    Ok(event)
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, count: usize) -> io::Result<&'a [u8]> {
+        if self.bytes.len() < count {
+            return Err(io::Error::from(io::ErrorKind::UnexpectedEof));
+        }
+
+        let (taken, rest) = self.bytes.split_at(count);
+        self.bytes = rest;
+
+        Ok(taken)
+    }
+
+    fn array<const N: usize>(&mut self) -> io::Result<[u8; N]> {
+        Ok(self.take(N)?.try_into().unwrap())
+    }
+
+    fn u8(&mut self) -> io::Result<u8> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u32(&mut self) -> io::Result<u32> {
+        Ok(u32::from_le_bytes(self.array()?))
+    }
+
+    fn i32(&mut self) -> io::Result<i32> {
+        Ok(i32::from_le_bytes(self.array()?))
+    }
+
+    fn f32(&mut self) -> io::Result<f32> {
+        Ok(f32::from_le_bytes(self.array()?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GAMEPAD_BUTTONS: usize = GamepadButton::DPadRight as usize + 1;
+    const GAMEPAD_AXES: usize = GamepadAxis::RightTrigger as usize + 1;
+
+    #[test]
+    fn every_input_event_round_trips() {
+        let keys = (0..=KeyCode::Unknown as usize).map(|index| KeyCode::from_index(index).unwrap());
+        let key_events = keys.flat_map(|key| [Event::KeyDown(key), Event::KeyUp(key)]).collect::<Vec<_>>();
+
+        let buttons = [MouseButton::Left, MouseButton::Right, MouseButton::Middle, MouseButton::Other(0), MouseButton::Other(2)];
+        let mouse_events = buttons.iter()
+            .flat_map(|&button| [Event::MouseDown(button), Event::MouseUp(button)])
+            .chain([
+                Event::MouseMoved(Vector2 { x: -12, y: 480 }),
+                Event::MouseMotion(Vector2 { x: 1.5, y: -0.25 }),
+                Event::MouseWheel(-1.0),
+            ])
+            .collect::<Vec<_>>();
+
+        let gamepad_events = (0..GAMEPAD_BUTTONS)
+            .map(|index| GamepadButton::from_index(index).unwrap())
+            .flat_map(|button| [Event::GamepadDown(button), Event::GamepadUp(button)])
+            .chain((0..GAMEPAD_AXES).map(|index| Event::GamepadAxisMoved(GamepadAxis::from_index(index).unwrap(), -0.5)))
+            .chain([Event::Focused(false), Event::Focused(true)])
+            .collect::<Vec<_>>();
+
+        let mut recording = InputRecording::new();
+        recording.push_frame(1.0 / 60.0, &key_events);
+        recording.push_frame(0.0, &[]);
+        recording.push_frame(0.02, &mouse_events);
+        recording.push_frame(0.5, &gamepad_events);
+
+        let decoded = InputRecording::decode(&recording.encode()).unwrap();
+        assert_eq!(decoded, recording);
+        assert_eq!(decoded.frames()[0].events, key_events);
+        assert_eq!(decoded.frames()[2].events, mouse_events);
+        assert_eq!(decoded.frames()[3].events, gamepad_events);
+    }
+
+    #[test]
+    fn key_indices_follow_the_variant_order() {
+        for index in 0..=KeyCode::Unknown as usize {
+            assert_eq!(KeyCode::from_index(index).unwrap() as usize, index);
+        }
+        for index in 0..GAMEPAD_BUTTONS {
+            assert_eq!(GamepadButton::from_index(index).unwrap() as usize, index);
+        }
+        for index in 0..GAMEPAD_AXES {
+            assert_eq!(GamepadAxis::from_index(index).unwrap() as usize, index);
+        }
+    }
+
+    #[test]
+    fn window_events_are_not_recorded() {
+        let mut recording = InputRecording::new();
+        recording.push_frame(0.1, &[
+            Event::Resized(Vector2 { x: 800, y: 600 }),
+            Event::Moved(Vector2 { x: 10, y: 20 }),
+            Event::Minimized(true),
+            Event::ScaleFactorChanged(1.5),
+            Event::CloseRequested,
+            Event::Char('a'),
+            Event::KeyDown(KeyCode::A),
+        ]);
+
+        assert_eq!(recording.frames()[0].events, [Event::KeyDown(KeyCode::A)]);
+    }
+
+    #[test]
+    fn frames_with_many_events_keep_them_all() {
+        let events = vec![Event::MouseWheel(1.0); u16::MAX as usize + 10];
+
+        let mut recording = InputRecording::new();
+        recording.push_frame(0.1, &events);
+
+        let decoded = InputRecording::decode(&recording.encode()).unwrap();
+        assert_eq!(decoded.frames()[0].events.len(), events.len());
+    }
+
+    #[test]
+    fn truncated_recordings_are_errors() {
+        let mut recording = InputRecording::new();
+        recording.push_frame(0.1, &[Event::KeyDown(KeyCode::Space)]);
+
+        let bytes = recording.encode();
+        assert!(InputRecording::decode(&bytes[..bytes.len() - 1]).is_err());
+        assert!(InputRecording::decode(b"NOPE").is_err());
+    }
+
+    #[test]
+    fn huge_frame_counts_are_errors() {
+        let mut bytes = MAGIC.to_vec();
+        bytes.push(VERSION);
+        bytes.extend_from_slice(&u32::MAX.to_le_bytes());
+
+        assert!(InputRecording::decode(&bytes).is_err());
+
+        // a frame claiming more events than the file holds fails the same way.
+        bytes.truncate(MAGIC.len() + 1);
+        bytes.extend_from_slice(&1u32.to_le_bytes());
+        bytes.extend_from_slice(&0.1f32.to_le_bytes());
+        bytes.extend_from_slice(&u32::MAX.to_le_bytes());
+
+        assert!(InputRecording::decode(&bytes).is_err());
+    }
+}
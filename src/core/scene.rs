@@ -0,0 +1,893 @@
+use std::{collections::{HashMap, HashSet}, fmt, fs, io, path::{Path, PathBuf}};
+
+use cgmath::{Deg, Vector3};
+
+use super::{camera::{Camera, Projection, Viewport}, entity::{Entity, EntityId, EntityJoin, Light, LightKind, MaterialRef, MeshRef, Transform}, prefab::{Overrides, Prefab, PrefabInstance, PrefabPart}, tween::Color};
+
+/// Mesh file of a scene, entities refer to it by name.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MeshAsset {
+    pub name: String,
+    pub path: PathBuf,
+    /// Keep the triangles after upload, so the mesh is picked by them instead of its bounds.
+    pub keep_geometry: bool,
+}
+
+/// Texture file of a scene, entities refer to it by name.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TextureAsset {
+    pub name: String,
+    pub path: PathBuf,
+}
+
+#[derive(Debug, Clone)]
+pub struct SceneCamera {
+    pub name: String,
+    pub camera: Camera,
+}
+
+/// Entity of a scene, with its assets and parent referred to by name.
+///
+/// Fields left out of the file are `None` and keep their default, or the value of the
+/// prefab root for an instance.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SceneEntity {
+    pub name: String,
+    /// Entity listed earlier in the scene this one is placed relative to.
+    pub parent: Option<String>,
+    /// Prefab this entity is an instance of, its other fields override the prefab root.
+    pub prefab: Option<String>,
+    pub position: Option<Vector3<f32>>,
+    /// Tait-Bryan angles in radians.
+    pub rotation: Option<Vector3<f32>>,
+    pub scale: Option<Vector3<f32>>,
+    pub mesh: Option<String>,
+    pub texture: Option<String>,
+    pub light: Option<Light>,
+    pub tags: Vec<String>,
+}
+
+impl SceneEntity {
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_owned(),
+            parent: None,
+            prefab: None,
+            position: None,
+            rotation: None,
+            scale: None,
+            mesh: None,
+            texture: None,
+            light: None,
+            tags: Vec::new(),
+        }
+    }
+
+    /// Transform with the fields left out at their default.
+    pub fn transform(&self) -> Transform {
+        let default = Transform::new();
+
+        Transform {
+            position: self.position.unwrap_or(default.position),
+            rotation: self.rotation.unwrap_or(default.rotation),
+            scale: self.scale.unwrap_or(default.scale),
+        }
+    }
+
+    /// This entity with the fields it leaves out taken from `base`, and the tags of both.
+    pub fn or(&self, base: &SceneEntity) -> SceneEntity {
+        let mut tags = base.tags.clone();
+        tags.extend(self.tags.iter().filter(|tag| !base.tags.contains(tag)).cloned());
+
+        SceneEntity {
+            name: self.name.clone(),
+            parent: self.parent.clone(),
+            prefab: None,
+            position: self.position.or(base.position),
+            rotation: self.rotation.or(base.rotation),
+            scale: self.scale.or(base.scale),
+            mesh: self.mesh.clone().or_else(|| base.mesh.clone()),
+            texture: self.texture.clone().or_else(|| base.texture.clone()),
+            light: self.light.or(base.light),
+            tags,
+        }
+    }
+
+    /// Leave out the fields `base` gives the same value and `overrides` does not hold,
+    /// the inverse of `or`.
+    fn except(mut self, base: &SceneEntity, overrides: Overrides) -> SceneEntity {
+        let transform = base.transform();
+
+        if !overrides.position && self.position == Some(transform.position) {
+            self.position = None;
+        }
+        if !overrides.rotation && self.rotation == Some(transform.rotation) {
+            self.rotation = None;
+        }
+        if !overrides.scale && self.scale == Some(transform.scale) {
+            self.scale = None;
+        }
+        if !overrides.mesh && self.mesh == base.mesh {
+            self.mesh = None;
+        }
+        if !overrides.texture && self.texture == base.texture {
+            self.texture = None;
+        }
+        if !overrides.light && self.light == base.light {
+            self.light = None;
+        }
+        self.tags.retain(|tag| !base.tags.contains(tag));
+
+        self
+    }
+}
+
+/// Assets, cameras and entities a game starts from.
+///
+/// Scenes are stored as text, one entry per line:
+/// ```text
+/// # comment
+/// mesh viking = res/Viking.obj, geometry
+/// texture viking = res/Viking.png
+/// prefab segment = res/Segment.prefab
+/// camera main = position 0 3 6, look_at -0.5 1 1, perspective 45 0.1 100
+/// entity cart = position -2 1 1, rotation 0 1.57 0, mesh viking, texture viking, tag vehicle
+/// entity lamp = parent cart, position 0 2 0, light point 1 0.9 0.7 2 10
+/// entity segment_1 = prefab segment, position 4 0 0
+/// ```
+/// Entity rotations are Tait-Bryan angles in radians. Cameras are turned with `look_at`
+/// from the position given before it, or `yaw_pitch_roll` in degrees, and project with
+/// `perspective`, `orthographic height near far` or `reversed_z fovy near` into
+/// `viewport x y width height`. Lights are `light directional r g b intensity` or
+/// `light point r g b intensity range`.
+///
+/// Assets, prefabs and parents are listed before the entities using them. Paths are
+/// relative to the working directory, written in double quotes when they hold a `,` or
+/// `#`. Children of an instance are named after it, `segment_1.rail` for the `rail`
+/// entity of the prefab above.
+#[derive(Debug, Clone, Default)]
+pub struct Scene {
+    pub meshes: Vec<MeshAsset>,
+    pub textures: Vec<TextureAsset>,
+    pub prefabs: Vec<Prefab>,
+    /// The first camera is the main one.
+    pub cameras: Vec<SceneCamera>,
+    /// Parents come before their children.
+    pub entities: Vec<SceneEntity>,
+}
+
+impl Scene {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Parse the scene at `path` and load the prefabs it lists.
+    pub fn load(path: &Path) -> io::Result<Self> {
+        let mut scene = Self::parse(&fs::read_to_string(path)?)?;
+        scene.load_prefabs()?;
+
+        Ok(scene)
+    }
+
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        fs::write(path, self.to_string())
+    }
+
+    pub fn parse(text: &str) -> io::Result<Self> {
+        let mut scene = Self::new();
+
+        for (number, line) in text.lines().enumerate() {
+            let line = strip_comment(line).trim();
+            if line.is_empty() {
+                continue;
+            }
+
+            let invalid = |reason: &str| io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("line {}: {reason}: {line}", number + 1)
+            );
+
+            let (head, properties) = line.split_once('=').ok_or_else(|| invalid("missing '='"))?;
+            let (kind, name) = head.trim().split_once(char::is_whitespace).ok_or_else(|| invalid("missing name"))?;
+            let name = word(name.trim()).ok_or_else(|| invalid("names are a single word"))?;
+
+            let mut properties = split_unquoted(properties, ',').into_iter().map(str::trim).filter(|property| !property.is_empty());
+
+            match kind {
+                "mesh" => {
+                    if scene.mesh_index(name).is_some() {
+                        return Err(invalid("duplicate mesh"));
+                    }
+
+                    let path = properties.next().ok_or_else(|| invalid("missing path"))?;
+                    let path = parse_path(path).ok_or_else(|| invalid("unterminated quoted path"))?;
+                    let keep_geometry = match (properties.next(), properties.next()) {
+                        (None, _) => false,
+                        (Some("geometry"), None) => true,
+                        _ => return Err(invalid("expected 'geometry'")),
+                    };
+
+                    scene.meshes.push(MeshAsset { name: name.to_owned(), path, keep_geometry });
+                }
+                "texture" => {
+                    if scene.texture_index(name).is_some() {
+                        return Err(invalid("duplicate texture"));
+                    }
+
+                    let path = match (properties.next(), properties.next()) {
+                        (Some(path), None) => parse_path(path).ok_or_else(|| invalid("unterminated quoted path"))?,
+                        _ => return Err(invalid("expected a path")),
+                    };
+
+                    scene.textures.push(TextureAsset { name: name.to_owned(), path });
+                }
+                "prefab" => {
+                    if scene.prefab(name).is_some() {
+                        return Err(invalid("duplicate prefab"));
+                    }
+
+                    let path = match (properties.next(), properties.next()) {
+                        (Some(path), None) => parse_path(path).ok_or_else(|| invalid("unterminated quoted path"))?,
+                        _ => return Err(invalid("expected a path")),
+                    };
+
+                    scene.prefabs.push(Prefab::new(name, &path));
+                }
+                "camera" => {
+                    let mut camera = Camera::new();
+                    for property in properties {
+                        Self::parse_camera_property(&mut camera, property).ok_or_else(|| invalid("unknown camera property"))?;
+                    }
+
+                    scene.cameras.push(SceneCamera { name: name.to_owned(), camera });
+                }
+                "entity" => {
+                    if scene.entities.iter().any(|entity| entity.name == name) {
+                        return Err(invalid("duplicate entity"));
+                    }
+
+                    let mut entity = SceneEntity::new(name);
+                    for property in properties {
+                        Self::parse_entity_property(&mut entity, property).ok_or_else(|| invalid("unknown entity property"))?;
+                    }
+
+                    if entity.mesh.as_deref().is_some_and(|mesh| scene.mesh_index(mesh).is_none()) {
+                        return Err(invalid("unknown mesh"));
+                    }
+                    if entity.texture.as_deref().is_some_and(|texture| scene.texture_index(texture).is_none()) {
+                        return Err(invalid("unknown texture"));
+                    }
+                    if entity.prefab.as_deref().is_some_and(|prefab| scene.prefab(prefab).is_none()) {
+                        return Err(invalid("unknown prefab"));
+                    }
+                    if entity.parent.as_deref().is_some_and(|parent| !scene.has_parent(parent)) {
+                        return Err(invalid("parent must be listed before"));
+                    }
+
+                    scene.entities.push(entity);
+                }
+                _ => return Err(invalid("expected 'mesh', 'texture', 'prefab', 'camera' or 'entity'")),
+            }
+        }
+
+        Ok(scene)
+    }
+
+    fn parse_camera_property(camera: &mut Camera, property: &str) -> Option<()> {
+        let (key, value) = split_property(property);
+
+        match key {
+            "position" => camera.set_position(vector(value)?),
+            "look_at" => camera.look_at(vector(value)?, Vector3::unit_y()),
+            "yaw_pitch_roll" => {
+                let [yaw, pitch, roll] = numbers(value)?;
+                camera.set_yaw_pitch_roll(Deg(yaw), Deg(pitch), Deg(roll));
+            }
+            "perspective" => {
+                let [fovy, near, far] = numbers(value)?;
+                camera.set_projection(Projection::Perspective { fovy: Deg(fovy), near, far });
+            }
+            "orthographic" => {
+                let [height, near, far] = numbers(value)?;
+                camera.set_projection(Projection::Orthographic { height, near, far });
+            }
+            "reversed_z" => {
+                let [fovy, near] = numbers(value)?;
+                camera.set_projection(Projection::ReversedZInfinite { fovy: Deg(fovy), near });
+            }
+            "viewport" => {
+                let [x, y, width, height] = numbers(value)?;
+                camera.set_viewport(Viewport { x, y, width, height });
+            }
+            _ => return None,
+        }
+
+        Some(())
+    }
+
+    fn parse_entity_property(entity: &mut SceneEntity, property: &str) -> Option<()> {
+        let (key, value) = split_property(property);
+
+        match key {
+            "position" => entity.position = Some(vector(value)?),
+            "rotation" => entity.rotation = Some(vector(value)?),
+            "scale" => entity.scale = Some(vector(value)?),
+            "parent" => entity.parent = Some(word(value)?.to_owned()),
+            "prefab" => entity.prefab = Some(word(value)?.to_owned()),
+            "mesh" => entity.mesh = Some(word(value)?.to_owned()),
+            "texture" => entity.texture = Some(word(value)?.to_owned()),
+            "tag" => entity.tags.push(word(value)?.to_owned()),
+            "light" => {
+                let (kind, value) = split_property(value);
+                entity.light = Some(match kind {
+                    "directional" => {
+                        let [r, g, b, intensity] = numbers(value)?;
+                        Light { kind: LightKind::Directional, color: Color::rgb(r, g, b), intensity }
+                    }
+                    "point" => {
+                        let [r, g, b, intensity, range] = numbers(value)?;
+                        Light { kind: LightKind::Point { range }, color: Color::rgb(r, g, b), intensity }
+                    }
+                    _ => return None,
+                });
+            }
+            _ => return None,
+        }
+
+        Some(())
+    }
+
+    pub fn mesh_index(&self, name: &str) -> Option<usize> {
+        self.meshes.iter().position(|mesh| mesh.name == name)
+    }
+
+    pub fn texture_index(&self, name: &str) -> Option<usize> {
+        self.textures.iter().position(|texture| texture.name == name)
+    }
+
+    pub fn prefab(&self, name: &str) -> Option<&Prefab> {
+        self.prefabs.iter().find(|prefab| prefab.name == name)
+    }
+
+    /// Whether `name` is a listed entity, or a child of a listed prefab instance.
+    fn has_parent(&self, name: &str) -> bool {
+        let (name, child) = match name.split_once('.') {
+            Some((instance, _)) => (instance, true),
+            None => (name, false),
+        };
+
+        self.entities.iter().any(|entity| entity.name == name && (!child || entity.prefab.is_some()))
+    }
+
+    /// Read the files of the prefabs and add their meshes and textures to the scene.
+    pub fn load_prefabs(&mut self) -> io::Result<()> {
+        for index in 0..self.prefabs.len() {
+            self.prefabs[index].load()?;
+            self.add_prefab_assets(index)?;
+        }
+
+        Ok(())
+    }
+
+    /// Load the prefabs whose file changed since and update their instances in `entities`.
+    /// Returns the names of the reloaded prefabs and the errors of those that failed, which
+    /// keep their instances as they were.
+    pub fn reload_prefabs(&mut self, entities: &mut EntityJoin) -> (Vec<String>, Vec<io::Error>) {
+        let mut reloaded = Vec::new();
+        let mut errors = Vec::new();
+
+        for index in 0..self.prefabs.len() {
+            if !self.prefabs[index].is_changed() {
+                continue;
+            }
+
+            if let Err(error) = self.prefabs[index].load().and_then(|()| self.add_prefab_assets(index)) {
+                errors.push(error);
+                continue;
+            }
+
+            let prefab = self.prefabs[index].clone();
+            prefab.update_instances(entities, self);
+            reloaded.push(prefab.name);
+        }
+
+        (reloaded, errors)
+    }
+
+    /// Add the assets of a prefab the scene does not list yet, the same name may not
+    /// point at two files.
+    fn add_prefab_assets(&mut self, index: usize) -> io::Result<()> {
+        let prefab = &self.prefabs[index];
+        let conflict = |kind: &str, name: &str| io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("{}: {kind} {name} is listed with another path", prefab.path.display())
+        );
+
+        let mut meshes = Vec::new();
+        for mesh in &prefab.scene.meshes {
+            match self.meshes.iter().find(|listed| listed.name == mesh.name) {
+                Some(listed) if listed.path != mesh.path => return Err(conflict("mesh", &mesh.name)),
+                Some(_) => (),
+                None => meshes.push(mesh.clone()),
+            }
+        }
+
+        let mut textures = Vec::new();
+        for texture in &prefab.scene.textures {
+            match self.textures.iter().find(|listed| listed.name == texture.name) {
+                Some(listed) if listed.path != texture.path => return Err(conflict("texture", &texture.name)),
+                Some(_) => (),
+                None => textures.push(texture.clone()),
+            }
+        }
+
+        self.meshes.extend(meshes);
+        self.textures.extend(textures);
+
+        Ok(())
+    }
+
+    /// Entity with the name, transform, tags and components of `scene_entity`, with its
+    /// assets looked up in this scene.
+    pub(crate) fn entity(&self, scene_entity: &SceneEntity) -> Entity {
+        let mut entity = Entity::new()
+            .with_name(&scene_entity.name)
+            .with_transform(scene_entity.transform());
+
+        for tag in &scene_entity.tags {
+            entity = entity.with_tag(tag);
+        }
+        if let Some(mesh) = scene_entity.mesh.as_deref().and_then(|mesh| self.mesh_index(mesh)) {
+            entity = entity.with(MeshRef(mesh));
+        }
+        if let Some(texture) = scene_entity.texture.as_deref().and_then(|texture| self.texture_index(texture)) {
+            entity = entity.with(MaterialRef(texture));
+        }
+        if let Some(light) = scene_entity.light {
+            entity = entity.with(light);
+        }
+
+        entity
+    }
+
+    /// Add the entities of the scene, with `MeshRef` and `MaterialRef` indices into
+    /// `meshes` and `textures`. Returns their ids in the order of `entities`, prefab
+    /// instances come with their children.
+    pub fn spawn(&self, entities: &mut EntityJoin) -> Vec<EntityId> {
+        let mut ids: HashMap<String, EntityId> = HashMap::new();
+
+        self.entities.iter().map(|scene_entity| {
+            let parent = scene_entity.parent.as_ref().and_then(|parent| ids.get(parent)).copied();
+
+            let id = match scene_entity.prefab.as_deref().and_then(|prefab| self.prefab(prefab)) {
+                Some(prefab) => {
+                    let id = prefab.instantiate(entities, scene_entity, parent, self);
+                    for (source, part) in Prefab::parts(entities, id) {
+                        ids.insert(format!("{}.{source}", scene_entity.name), part);
+                    }
+                    id
+                }
+                None => {
+                    let entity = self.entity(scene_entity);
+                    match parent {
+                        Some(parent) => entities.add_child(parent, entity),
+                        None => entities.add(entity),
+                    }
+                }
+            };
+            ids.insert(scene_entity.name.clone(), id);
+
+            id
+        }).collect()
+    }
+
+    /// Scene of `entities` as they are now, e.g. to save a game, with the assets, prefabs
+    /// and cameras of `scene`. Entities without a name, or sharing it with another, are
+    /// saved as `entity_<index>`, with `_` added until no other entity has that name, so
+    /// children can refer to them. Prefab instances keep the
+    /// fields they override or that differ from the prefab, their children are left to it.
+    pub fn from_entities(entities: &EntityJoin, scene: Scene) -> Self {
+        let mut name_counts: HashMap<&str, usize> = HashMap::new();
+        for (_, entity) in entities.iter() {
+            if let Some(name) = entity.name() {
+                *name_counts.entry(name).or_default() += 1;
+            }
+        }
+
+        let mut names: HashMap<EntityId, String> = HashMap::new();
+        let mut taken: HashSet<String> = name_counts.keys().map(|&name| name.to_owned()).collect();
+        for (id, entity) in entities.iter() {
+            let name = match entity.name().and_then(word) {
+                Some(name) if name_counts[name] == 1 => name.to_owned(),
+                _ => {
+                    let mut name = format!("entity_{}", id.index());
+                    while taken.contains(&name) {
+                        name.push('_');
+                    }
+                    taken.insert(name.clone());
+                    name
+                }
+            };
+            names.insert(id, name);
+        }
+        let name_of = |id: EntityId| names[&id].clone();
+
+        let defaults = SceneEntity::new("");
+
+        // depth first from the roots, so parents are listed before their children.
+        let mut stack: Vec<EntityId> = entities.ids().filter(|&id| entities.parent(id).is_none()).collect();
+        stack.reverse();
+
+        let mut scene_entities = Vec::new();
+        while let Some(id) = stack.pop() {
+            let children: Vec<EntityId> = entities.children(id).collect();
+            stack.extend(children.into_iter().rev());
+
+            if entities.component::<PrefabPart>(id).is_some() {
+                continue;
+            }
+
+            let transform = entities.component::<Transform>(id).copied().unwrap_or_default();
+            let tags = entities.get(id).map(Entity::tags).unwrap_or_default();
+
+            let scene_entity = SceneEntity {
+                name: name_of(id),
+                parent: entities.parent(id).map(name_of),
+                prefab: None,
+                position: Some(transform.position),
+                rotation: Some(transform.rotation),
+                scale: Some(transform.scale),
+                mesh: entities.component::<MeshRef>(id).and_then(|mesh| scene.meshes.get(mesh.0)).map(|mesh| mesh.name.clone()),
+                texture: entities.component::<MaterialRef>(id).and_then(|texture| scene.textures.get(texture.0)).map(|texture| texture.name.clone()),
+                light: entities.component::<Light>(id).copied(),
+                tags: tags.iter().filter_map(|tag| word(tag)).map(str::to_owned).collect(),
+            };
+
+            scene_entities.push(match entities.component::<PrefabInstance>(id) {
+                Some(instance) => {
+                    let root = scene.prefab(&instance.prefab).and_then(Prefab::root).unwrap_or(&defaults);
+                    SceneEntity { prefab: Some(instance.prefab.clone()), ..scene_entity.except(root, instance.overrides) }
+                }
+                None => scene_entity.except(&defaults, Overrides::default()),
+            });
+        }
+
+        Self {
+            entities: scene_entities,
+            ..scene
+        }
+    }
+}
+
+impl fmt::Display for Scene {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for mesh in &self.meshes {
+            write!(f, "mesh {} = {}", mesh.name, PathText(&mesh.path))?;
+            if mesh.keep_geometry {
+                write!(f, ", geometry")?;
+            }
+            writeln!(f)?;
+        }
+
+        for texture in &self.textures {
+            writeln!(f, "texture {} = {}", texture.name, PathText(&texture.path))?;
+        }
+
+        for prefab in &self.prefabs {
+            writeln!(f, "prefab {} = {}", prefab.name, PathText(&prefab.path))?;
+        }
+
+        for SceneCamera { name, camera } in &self.cameras {
+            let (yaw, pitch, roll) = camera.yaw_pitch_roll();
+            write!(f, "camera {name} = position {}, yaw_pitch_roll {} {} {}", Numbers(camera.position()), yaw.0, pitch.0, roll.0)?;
+
+            match camera.projection() {
+                Projection::Perspective { fovy, near, far } => write!(f, ", perspective {} {near} {far}", fovy.0)?,
+                Projection::Orthographic { height, near, far } => write!(f, ", orthographic {height} {near} {far}")?,
+                Projection::ReversedZInfinite { fovy, near } => write!(f, ", reversed_z {} {near}", fovy.0)?,
+            }
+
+            let viewport = camera.viewport();
+            if viewport != Viewport::FULL {
+                write!(f, ", viewport {} {} {} {}", viewport.x, viewport.y, viewport.width, viewport.height)?;
+            }
+            writeln!(f)?;
+        }
+
+        for entity in &self.entities {
+            let mut properties = Vec::new();
+
+            if let Some(prefab) = &entity.prefab {
+                properties.push(format!("prefab {prefab}"));
+            }
+            if let Some(parent) = &entity.parent {
+                properties.push(format!("parent {parent}"));
+            }
+            if let Some(position) = entity.position {
+                properties.push(format!("position {}", Numbers(position)));
+            }
+            if let Some(rotation) = entity.rotation {
+                properties.push(format!("rotation {}", Numbers(rotation)));
+            }
+            if let Some(scale) = entity.scale {
+                properties.push(format!("scale {}", Numbers(scale)));
+            }
+            if let Some(mesh) = &entity.mesh {
+                properties.push(format!("mesh {mesh}"));
+            }
+            if let Some(texture) = &entity.texture {
+                properties.push(format!("texture {texture}"));
+            }
+            if let Some(Light { kind, color, intensity }) = entity.light {
+                properties.push(match kind {
+                    LightKind::Directional => format!("light directional {} {} {} {intensity}", color.r, color.g, color.b),
+                    LightKind::Point { range } => format!("light point {} {} {} {intensity} {range}", color.r, color.g, color.b),
+                });
+            }
+            for tag in &entity.tags {
+                properties.push(format!("tag {tag}"));
+            }
+
+            writeln!(f, "entity {} = {}", entity.name, properties.join(", "))?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Components of a vector separated by spaces.
+struct Numbers(Vector3<f32>);
+
+impl fmt::Display for Numbers {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {} {}", self.0.x, self.0.y, self.0.z)
+    }
+}
+
+/// A path as it is written in a scene, quoted with `\\` and `\"` escaped when it holds a
+/// separator, a quote or surrounding spaces.
+struct PathText<'a>(&'a Path);
+
+impl fmt::Display for PathText<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let text = self.0.display().to_string();
+
+        let needs_quotes = text.is_empty()
+            || text.trim() != text
+            || text.contains(|c: char| ",#\"".contains(c));
+        if !needs_quotes {
+            return write!(f, "{text}");
+        }
+
+        write!(f, "\"{}\"", text.replace('\\', "\\\\").replace('"', "\\\""))
+    }
+}
+
+/// Path written by `PathText`, `None` when a quote is left open or followed by more text.
+fn parse_path(text: &str) -> Option<PathBuf> {
+    let Some(quoted) = text.strip_prefix('"') else {
+        return Some(PathBuf::from(text));
+    };
+
+    let mut path = String::new();
+    let mut chars = quoted.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => path.push(chars.next()?),
+            '"' => return chars.as_str().is_empty().then(|| PathBuf::from(path)),
+            c => path.push(c),
+        }
+    }
+
+    None
+}
+
+/// Byte offsets of the characters of `text` outside quoted paths.
+fn unquoted(text: &str) -> impl Iterator<Item = (usize, char)> + '_ {
+    let mut is_quoted = false;
+    let mut is_escaped = false;
+
+    text.char_indices().filter(move |&(_, c)| {
+        let was_quoted = is_quoted;
+        match c {
+            _ if is_escaped => is_escaped = false,
+            '\\' if is_quoted => is_escaped = true,
+            '"' => is_quoted = !is_quoted,
+            _ => {}
+        }
+
+        !was_quoted && c != '"'
+    })
+}
+
+/// `line` up to its first `#` outside quotes.
+fn strip_comment(line: &str) -> &str {
+    match unquoted(line).find(|&(_, c)| c == '#') {
+        Some((index, _)) => &line[..index],
+        None => line,
+    }
+}
+
+/// `text` split at every `separator` outside quotes.
+fn split_unquoted(text: &str, separator: char) -> Vec<&str> {
+    let mut parts = Vec::new();
+
+    let mut start = 0;
+    for (index, _) in unquoted(text).filter(|&(_, c)| c == separator) {
+        parts.push(&text[start..index]);
+        start = index + separator.len_utf8();
+    }
+    parts.push(&text[start..]);
+
+    parts
+}
+
+/// Property name and the rest of the text after it.
+fn split_property(property: &str) -> (&str, &str) {
+    match property.split_once(char::is_whitespace) {
+        Some((key, value)) => (key, value.trim()),
+        None => (property, ""),
+    }
+}
+
+/// `text` when it can be written back as a name, without spaces or separators.
+fn word(text: &str) -> Option<&str> {
+    let is_word = !text.is_empty() && !text.contains(|c: char| c.is_whitespace() || ",=#".contains(c));
+
+    is_word.then_some(text)
+}
+
+/// Exactly `N` numbers separated by whitespace.
+fn numbers<const N: usize>(text: &str) -> Option<[f32; N]> {
+    let mut words = text.split_whitespace();
+
+    let mut values = [0.0; N];
+    for value in &mut values {
+        *value = words.next()?.parse().ok()?;
+    }
+
+    words.next().is_none().then_some(values)
+}
+
+fn vector(text: &str) -> Option<Vector3<f32>> {
+    numbers(text).map(Vector3::from)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cgmath::assert_abs_diff_eq;
+
+    const SCENE: &str = "\
+# a cart with a lamp on top.
+mesh cart = res/Cart.obj, geometry
+texture wood = res/Wood.png
+camera main = position 0 3 6, look_at 0 1 0
+camera map = position 0 10 0, yaw_pitch_roll 0 -90 0, orthographic 20 0.1 50, viewport 0.7 0.05 0.25 0.25
+entity cart = position -2 1 1, rotation 0 1.5 0, mesh cart, texture wood, tag vehicle
+entity lamp = parent cart, position 0 2 0, scale 0.5 0.5 0.5, light point 1 0.9 0.7 2 10
+entity sun = light directional 1 1 1 0.8
+";
+
+    #[test]
+    fn scene_text_round_trips() {
+        let scene = Scene::parse(SCENE).unwrap();
+
+        assert_eq!(scene.meshes, [MeshAsset { name: "cart".into(), path: "res/Cart.obj".into(), keep_geometry: true }]);
+        assert_eq!(scene.entities.len(), 3);
+        assert_eq!(scene.entities[1].parent.as_deref(), Some("cart"));
+        assert_eq!(scene.entities[1].light.unwrap().kind, LightKind::Point { range: 10.0 });
+        assert_eq!(scene.cameras[1].camera.viewport(), Viewport { x: 0.7, y: 0.05, width: 0.25, height: 0.25 });
+
+        let saved = Scene::parse(&scene.to_string()).unwrap();
+
+        assert_eq!(saved.meshes, scene.meshes);
+        assert_eq!(saved.textures, scene.textures);
+        assert_eq!(saved.entities, scene.entities);
+        for (saved, camera) in saved.cameras.iter().zip(&scene.cameras) {
+            assert_eq!(saved.name, camera.name);
+            assert_eq!(saved.camera.projection(), camera.camera.projection());
+            assert_eq!(saved.camera.viewport(), camera.camera.viewport());
+            assert_abs_diff_eq!(saved.camera.position(), camera.camera.position());
+            assert_abs_diff_eq!(saved.camera.forward(), camera.camera.forward(), epsilon = 1e-5);
+            assert_abs_diff_eq!(saved.camera.up(), camera.camera.up(), epsilon = 1e-5);
+        }
+    }
+
+    #[test]
+    fn parse_reports_the_line_of_unknown_references() {
+        let error = Scene::parse("mesh cart = res/Cart.obj\nentity cart = mesh wagon\n").unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+        assert!(error.to_string().starts_with("line 2: unknown mesh"), "{error}");
+
+        let error = Scene::parse("entity lamp = parent cart\nentity cart =\n").unwrap_err();
+        assert!(error.to_string().starts_with("line 1: parent must be listed before"), "{error}");
+
+        assert!(Scene::parse("entity cart = position 1 2\n").is_err());
+    }
+
+    #[test]
+    fn spawned_entities_are_saved_back() {
+        let scene = Scene::parse(SCENE).unwrap();
+
+        let mut entities = EntityJoin::new();
+        let ids = scene.spawn(&mut entities);
+
+        assert_eq!(entities.parent(ids[1]), Some(ids[0]));
+        assert_eq!(entities.component::<MeshRef>(ids[0]), Some(&MeshRef(0)));
+        assert_eq!(entities.component::<MaterialRef>(ids[0]), Some(&MaterialRef(0)));
+        assert!(entities.get(ids[0]).unwrap().has_tag("vehicle"));
+
+        // unnamed entities are saved under their index.
+        let unnamed = entities.add(Entity::new());
+
+        let saved = Scene::from_entities(&entities, Scene { cameras: Vec::new(), ..scene.clone() });
+
+        assert_eq!(saved.entities[..3], scene.entities[..]);
+        assert_eq!(saved.entities[3].name, format!("entity_{}", unnamed.index()));
+    }
+
+    #[test]
+    fn generated_names_skip_the_names_in_use() {
+        let mut entities = EntityJoin::new();
+        let first = entities.add(Entity::new());
+        let taken = format!("entity_{}", first.index());
+        entities.add(Entity::new().with_name(&taken));
+        entities.add(Entity::new().with_name(&format!("{taken}_")));
+        let child = entities.add_child(first, Entity::new());
+
+        let saved = Scene::from_entities(&entities, Scene::new());
+        let names: Vec<&str> = saved.entities.iter().map(|entity| entity.name.as_str()).collect();
+
+        assert_eq!(names[0], format!("{taken}__"));
+        assert_eq!(saved.entities[1].parent.as_deref(), Some(names[0]));
+        assert_eq!(names.iter().collect::<HashSet<_>>().len(), names.len());
+        assert!(names.contains(&format!("entity_{}", child.index()).as_str()));
+
+        Scene::parse(&saved.to_string()).unwrap();
+    }
+
+    #[test]
+    fn paths_with_separators_are_quoted() {
+        let paths = ["res/Cart, old.obj", "res/#1.png", "res/\"quoted\" \\ dir.prefab", " res/padded.obj "];
+
+        let mut scene = Scene::new();
+        scene.meshes.push(MeshAsset { name: "cart".into(), path: paths[0].into(), keep_geometry: true });
+        scene.textures.push(TextureAsset { name: "first".into(), path: paths[1].into() });
+        scene.prefabs.push(Prefab::new("segment", Path::new(paths[2])));
+        scene.textures.push(TextureAsset { name: "padded".into(), path: paths[3].into() });
+
+        let text = scene.to_string();
+        assert!(text.contains("mesh cart = \"res/Cart, old.obj\", geometry"), "{text}");
+        assert!(text.contains("texture first = \"res/#1.png\""), "{text}");
+
+        let saved = Scene::parse(&text).unwrap();
+        assert_eq!(saved.meshes, scene.meshes);
+        assert_eq!(saved.textures, scene.textures);
+        assert_eq!(saved.prefabs[0].path, Path::new(paths[2]));
+
+        assert!(Scene::parse("texture wood = \"res/Wood.png\n").is_err());
+        assert!(Scene::parse("texture wood = \"res/Wood\".png\n").is_err());
+    }
+
+    #[test]
+    fn a_bad_prefab_does_not_stop_the_others_from_reloading() {
+        let dir = std::env::temp_dir().join(format!("engine_prefab_reload_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("bad.prefab"), "entity broken = mesh missing\n").unwrap();
+        fs::write(dir.join("good.prefab"), "entity post = position 0 1 0\n").unwrap();
+
+        let mut scene = Scene::parse(&format!(
+            "prefab bad = {}\nprefab good = {}\n",
+            dir.join("bad.prefab").display(),
+            dir.join("good.prefab").display()
+        )).unwrap();
+
+        let (reloaded, errors) = scene.reload_prefabs(&mut EntityJoin::new());
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(reloaded, ["good"]);
+        assert_eq!(errors.len(), 1);
+        assert!(scene.prefab("good").unwrap().root().is_some());
+    }
+}
\ No newline at end of file
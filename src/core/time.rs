@@ -1,65 +1,546 @@
 use std::{thread, time::{Duration, Instant}};
 
-const SAMPLE_COUNT: usize = 5;
-const SAMPLE_COUNT_FLOAT: f32 = SAMPLE_COUNT as f32;
-
-pub struct Fps {
-    counter: Instant,
-    frame_time_prefer: u32, // unit microseconds
-    samples: [u32; SAMPLE_COUNT],
-    current_frame: usize,
-    delta_frame: u32,
+/// Frames kept for the rolling statistics.
+const SAMPLE_COUNT: usize = 240;
+
+/// The limiter sleeps until this long before the frame deadline and spins the rest,
+/// since a sleep can overshoot by about a scheduler tick.
+const SPIN_MARGIN: Duration = Duration::from_millis(2);
+
+/// Frame time statistics over the last `SAMPLE_COUNT` frames, in seconds.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct FrameStats {
+    pub min: f32,
+    pub max: f32,
+    pub average: f32,
+    pub p50: f32,
+    pub p99: f32,
 }
 
-impl Fps {
+/// Frame clock of the game loop, call `tick` once per frame.
+pub struct Time {
+    start: Instant,
+    last_tick: Instant,
+
+    delta: Duration,
+    elapsed: Duration,
+    frame_count: u64,
+
+    scale: f32,
+    is_paused: bool,
+    frame_limit: Option<Duration>,
+
+    samples: [f32; SAMPLE_COUNT],
+    sample_count: usize,
+    next_sample: usize,
+}
+
+impl Time {
     pub fn new() -> Self {
-        const DEFAULT_PREFER_FPS: f32 = 60.0;
+        let now = Instant::now();
 
         Self {
-            counter: Instant::now(),
-            frame_time_prefer: (1000_000.0_f32 / DEFAULT_PREFER_FPS) as u32,
-            samples: [0; SAMPLE_COUNT],
-            current_frame: 0,
-            delta_frame: 0,
+            start: now,
+            last_tick: now,
+
+            delta: Duration::ZERO,
+            elapsed: Duration::ZERO,
+            frame_count: 0,
+
+            scale: 1.0,
+            is_paused: false,
+            frame_limit: None,
+
+            samples: [0.0; SAMPLE_COUNT],
+            sample_count: 0,
+            next_sample: 0,
+        }
+    }
+
+    /// End the current frame, waiting first if a frame limit is set.
+    pub fn tick(&mut self) {
+        if let Some(frame_limit) = self.frame_limit {
+            let deadline = self.last_tick + frame_limit;
+
+            if let Some(sleep) = sleep_time(Instant::now(), deadline) {
+                thread::sleep(sleep);
+            }
+            while Instant::now() < deadline {
+                std::hint::spin_loop();
+            }
         }
+
+        let now = Instant::now();
+        self.delta = now - self.last_tick;
+        self.last_tick = now;
+
+        self.elapsed += self.delta.mul_f32(self.effective_scale());
+        self.frame_count += 1;
+
+        self.push_sample(self.delta.as_secs_f32());
     }
 
-    pub fn set_prefer_fps(&mut self, prefer_fps: f32) {
-        self.frame_time_prefer = (1000_000.0_f32 / prefer_fps) as u32;
+    fn push_sample(&mut self, seconds: f32) {
+        self.samples[self.next_sample] = seconds;
+        self.next_sample = (self.next_sample + 1) % SAMPLE_COUNT;
+        self.sample_count = (self.sample_count + 1).min(SAMPLE_COUNT);
     }
 
-    /// Call this function in game loop to update its inner status.
-    pub fn tick_frame(&mut self) {
-        let time_elapsed = self.counter.elapsed();
-        self.counter = Instant::now();
+    fn effective_scale(&self) -> f32 {
+        if self.is_paused { 0.0 } else { self.scale }
+    }
 
-        self.delta_frame = time_elapsed.subsec_micros();
-        self.samples[self.current_frame] = self.delta_frame;
-        self.current_frame = (self.current_frame + 1) % SAMPLE_COUNT;
+    /// Game time of the last frame in seconds, scaled and 0 while paused.
+    pub fn delta_time(&self) -> f32 {
+        self.delta.as_secs_f32() * self.effective_scale()
     }
 
-    // TODO: this function seems not work.
-    pub fn keep_fps(&self) {
-        if self.frame_time_prefer > self.delta_frame {
-            let delay = Duration::from_micros((self.frame_time_prefer - self.delta_frame) as u64);
+    /// Wall clock time of the last frame in seconds.
+    pub fn unscaled_delta_time(&self) -> f32 {
+        self.delta.as_secs_f32()
+    }
 
-            thread::sleep(delay);
-        }
+    /// Game time since start in seconds, the sum of every scaled delta.
+    pub fn elapsed(&self) -> f64 {
+        self.elapsed.as_secs_f64()
     }
 
-    /// Calculate the current FPS.
+    /// Wall clock time since start in seconds.
+    pub fn real_elapsed(&self) -> f64 {
+        self.start.elapsed().as_secs_f64()
+    }
+
+    /// Frames ticked since start.
+    pub fn frame_count(&self) -> u64 {
+        self.frame_count
+    }
+
+    pub fn time_scale(&self) -> f32 {
+        self.scale
+    }
+
+    /// Speed of game time relative to the wall clock, e.g. 0.5 for slow motion.
+    pub fn set_time_scale(&mut self, scale: f32) {
+        self.scale = scale.max(0.0);
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.is_paused
+    }
+
+    pub fn set_paused(&mut self, is_paused: bool) {
+        self.is_paused = is_paused;
+    }
+
+    /// Cap the frame rate, `None` or a rate that isn't positive runs as fast as possible.
+    pub fn set_frame_limit(&mut self, fps: Option<f32>) {
+        self.frame_limit = fps
+            .filter(|fps| *fps > 0.0)
+            .and_then(|fps| Duration::try_from_secs_f32(1.0 / fps).ok());
+    }
+
+    /// Frames per second averaged over the sampled frames.
     pub fn fps(&self) -> f32 {
-        let mut sum = 0_u32;
-        self.samples.iter().for_each(|val| {
-            sum += val;
+        let average = self.stats().average;
+
+        if average > 0.0 { 1.0 / average } else { 0.0 }
+    }
+
+    pub fn stats(&self) -> FrameStats {
+        if self.sample_count == 0 {
+            return FrameStats::default();
+        }
+
+        let mut samples = self.samples[..self.sample_count].to_vec();
+        samples.sort_by(f32::total_cmp);
+
+        let percentile = |p: f32| samples[((samples.len() - 1) as f32 * p).round() as usize];
+
+        FrameStats {
+            min: samples[0],
+            max: samples[samples.len() - 1],
+            average: samples.iter().sum::<f32>() / samples.len() as f32,
+            p50: percentile(0.5),
+            p99: percentile(0.99),
+        }
+    }
+
+    /// Count of sampled frames per `bucket_width` seconds of frame time, starting at 0.
+    /// The last bucket also holds every longer frame.
+    pub fn histogram(&self, bucket_width: f32, bucket_count: usize) -> Vec<u32> {
+        let mut buckets = vec![0; bucket_count];
+        if bucket_count == 0 {
+            return buckets;
+        }
+
+        for sample in &self.samples[..self.sample_count] {
+            let bucket = ((sample / bucket_width) as usize).min(bucket_count - 1);
+            buckets[bucket] += 1;
+        }
+
+        buckets
+    }
+}
+
+impl Default for Time {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// How long the limiter can sleep before spinning up to `deadline`, `None` if it should only spin.
+fn sleep_time(now: Instant, deadline: Instant) -> Option<Duration> {
+    deadline
+        .checked_duration_since(now + SPIN_MARGIN)
+        .filter(|sleep| !sleep.is_zero())
+}
+
+/// Turns variable frame times into a whole number of fixed simulation steps.
+pub struct FixedTimestep {
+    step: f32, // unit seconds
+    max_steps: u32,
+    accumulator: f32,
+}
+
+impl FixedTimestep {
+    /// Panics unless `tick_rate` is positive.
+    pub fn new(tick_rate: f32) -> Self {
+        const DEFAULT_MAX_STEPS: u32 = 8;
+
+        Self {
+            step: Self::step_of(tick_rate),
+            max_steps: DEFAULT_MAX_STEPS,
+            accumulator: 0.0,
+        }
+    }
+
+    /// Simulation steps per second, panics unless `tick_rate` is positive.
+    pub fn set_tick_rate(&mut self, tick_rate: f32) {
+        self.step = Self::step_of(tick_rate);
+    }
+
+    fn step_of(tick_rate: f32) -> f32 {
+        assert!(tick_rate > 0.0 && tick_rate.is_finite(), "Tick rate must be positive, got {tick_rate}!");
+
+        1.0 / tick_rate
+    }
+
+    /// Most steps a single frame may catch up on, the time past it is dropped so a long hitch
+    /// slows the simulation down instead of stalling the next frames too.
+    pub fn set_max_steps(&mut self, max_steps: u32) {
+        self.max_steps = max_steps;
+    }
+
+    /// Length of one step in seconds.
+    pub fn step(&self) -> f32 {
+        self.step
+    }
+
+    /// Add the frame time and return how many steps to simulate this frame.
+    pub fn advance(&mut self, delta_time: f32) -> u32 {
+        self.accumulator += delta_time;
+
+        let mut steps = 0;
+        while self.accumulator >= self.step {
+            if steps == self.max_steps {
+                self.accumulator = 0.0;
+                break;
+            }
+
+            self.accumulator -= self.step;
+            steps += 1;
+        }
+
+        steps
+    }
+
+    /// How far the frame is between the last step and the next one, in 0..1, for interpolating
+    /// the rendered state.
+    pub fn alpha(&self) -> f32 {
+        // the accumulator is below one step, but the division may still round up to 1.
+        (self.accumulator / self.step).clamp(0.0, 1.0 - f32::EPSILON)
+    }
+}
+
+/// Handle to a scheduled timer, used to cancel it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct TimerId(u64);
+
+struct Timer {
+    id: TimerId,
+    remaining: f32,
+    /// Period of a repeating timer, `None` for a one-shot.
+    interval: Option<f32>,
+    callback: Box<dyn FnMut()>,
+}
+
+/// One-shot and repeating callbacks driven by game time, so they stop while paused and follow the time scale.
+#[derive(Default)]
+pub struct Scheduler {
+    timers: Vec<Timer>,
+    next_id: u64,
+}
+
+impl Scheduler {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Call `callback` once after `delay` seconds.
+    pub fn after(&mut self, delay: f32, callback: impl FnMut() + 'static) -> TimerId {
+        self.schedule(delay, None, Box::new(callback))
+    }
+
+    /// Call `callback` every `interval` seconds, starting one interval from now.
+    pub fn every(&mut self, interval: f32, callback: impl FnMut() + 'static) -> TimerId {
+        self.schedule(interval, Some(interval), Box::new(callback))
+    }
+
+    fn schedule(&mut self, delay: f32, interval: Option<f32>, callback: Box<dyn FnMut()>) -> TimerId {
+        let id = TimerId(self.next_id);
+        self.next_id += 1;
+
+        self.timers.push(Timer { id, remaining: delay, interval, callback });
+
+        id
+    }
+
+    pub fn cancel(&mut self, id: TimerId) {
+        self.timers.retain(|timer| timer.id != id);
+    }
+
+    /// True until a one-shot fires or the timer is cancelled.
+    pub fn is_active(&self, id: TimerId) -> bool {
+        self.timers.iter().any(|timer| timer.id == id)
+    }
+
+    /// Seconds left before the timer fires next.
+    pub fn remaining(&self, id: TimerId) -> Option<f32> {
+        self.timers.iter().find(|timer| timer.id == id).map(|timer| timer.remaining)
+    }
+
+    /// Advance by the game time of the last frame.
+    pub fn update(&mut self, time: &Time) {
+        self.advance(time.delta_time());
+    }
+
+    /// Advance by `delta_time` seconds of game time, firing every timer that comes due.
+    /// A repeating timer fires once for each interval that passed.
+    pub fn advance(&mut self, delta_time: f32) {
+        // nothing is due while the game time stands still.
+        if delta_time <= 0.0 {
+            return;
+        }
+
+        self.timers.retain_mut(|timer| {
+            timer.remaining -= delta_time;
+
+            while timer.remaining <= 0.0 {
+                (timer.callback)();
+
+                match timer.interval {
+                    // a zero interval would never catch up, fire it once per frame instead.
+                    Some(interval) if interval > 0.0 => timer.remaining += interval,
+                    Some(_) => {
+                        timer.remaining = 0.0;
+                        return true;
+                    }
+                    None => return false,
+                }
+            }
+
+            true
         });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{cell::Cell, rc::Rc};
 
-        1000_000.0_f32 / (sum as f32 / SAMPLE_COUNT_FLOAT)
+    use super::*;
+
+    #[test]
+    fn frame_limit_ignores_rates_that_are_not_positive() {
+        let mut time = Time::new();
+
+        for fps in [0.0, -30.0, f32::NAN, f32::MIN_POSITIVE / 2.0] {
+            time.set_frame_limit(Some(fps));
+            assert_eq!(time.frame_limit, None, "{fps}");
+        }
+
+        time.set_frame_limit(Some(50.0));
+        assert_eq!(time.frame_limit, Some(Duration::from_millis(20)));
     }
 
-    /// Return current delta time in seconds
-    /// this function ignore its second part, since the second is mostly zero.
-    pub fn delta_time(&self) -> f32 {
-        self.delta_frame as f32 / 1000_000.0_f32 // time in second
+    #[test]
+    fn sleep_time_leaves_the_spin_margin() {
+        let now = Instant::now();
+
+        let deadline = now + Duration::from_millis(10);
+        assert_eq!(sleep_time(now, deadline), Some(Duration::from_millis(10) - SPIN_MARGIN));
+
+        assert_eq!(sleep_time(now, now + SPIN_MARGIN), None);
+        assert_eq!(sleep_time(now, now + SPIN_MARGIN / 2), None);
+        assert_eq!(sleep_time(now + Duration::from_millis(5), now), None);
+    }
+
+    #[test]
+    fn stats_over_the_sampled_frames() {
+        let mut time = Time::new();
+        assert_eq!(time.stats(), FrameStats::default());
+        assert_eq!(time.fps(), 0.0);
+
+        for sample in [0.01, 0.02, 0.03, 0.04, 0.05] {
+            time.push_sample(sample);
+        }
+
+        let stats = time.stats();
+        assert_eq!(stats.min, 0.01);
+        assert_eq!(stats.max, 0.05);
+        assert!((stats.average - 0.03).abs() < 1e-6);
+        assert_eq!(stats.p50, 0.03);
+        assert_eq!(stats.p99, 0.05);
+        assert!((time.fps() - 1.0 / 0.03).abs() < 1e-3);
+
+        assert_eq!(time.histogram(0.02, 3), vec![1, 2, 2]);
+        assert!(time.histogram(0.02, 0).is_empty());
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn stats_keep_only_the_latest_samples() {
+        let mut time = Time::new();
+
+        time.push_sample(1.0);
+        for _ in 0..SAMPLE_COUNT {
+            time.push_sample(0.5);
+        }
+
+        let stats = time.stats();
+        assert_eq!(stats.min, 0.5);
+        assert_eq!(stats.max, 0.5);
+    }
+
+    #[test]
+    fn fixed_steps_follow_the_accumulated_time() {
+        let mut timestep = FixedTimestep::new(50.0);
+        assert_eq!(timestep.step(), 0.02);
+
+        assert_eq!(timestep.advance(0.01), 0);
+        assert_eq!(timestep.advance(0.015), 1);
+        assert_eq!(timestep.advance(0.05), 2);
+        assert!((timestep.alpha() - 0.75).abs() < 1e-3, "{}", timestep.alpha());
+
+        timestep.set_tick_rate(100.0);
+        assert_eq!(timestep.advance(0.0), 1);
+    }
+
+    #[test]
+    fn a_long_hitch_is_clamped_to_max_steps() {
+        let mut timestep = FixedTimestep::new(60.0);
+        timestep.set_max_steps(4);
+
+        assert_eq!(timestep.advance(2.0), 4);
+        // the rest of the hitch is dropped, not caught up on later.
+        assert_eq!(timestep.alpha(), 0.0);
+        assert_eq!(timestep.advance(0.0), 0);
+    }
+
+    #[test]
+    fn alpha_stays_below_1() {
+        let mut timestep = FixedTimestep::new(60.0);
+
+        for frame in 0..1000 {
+            timestep.advance(0.001 + (frame % 37) as f32 * 0.0007);
+
+            let alpha = timestep.alpha();
+            assert!((0.0..1.0).contains(&alpha), "{alpha} at frame {frame}");
+        }
+    }
+
+    #[test]
+    #[should_panic]
+    fn tick_rates_must_be_positive() {
+        FixedTimestep::new(0.0);
+    }
+
+    /// A clock whose last frame took `delta` seconds of wall time.
+    fn time_with_delta(delta: f32) -> Time {
+        let mut time = Time::new();
+        time.delta = Duration::from_secs_f32(delta);
+        time
+    }
+
+    /// Scheduler with a counter the callback of `schedule` bumps.
+    fn counting(schedule: impl FnOnce(&mut Scheduler, Box<dyn FnMut()>) -> TimerId) -> (Scheduler, TimerId, Rc<Cell<u32>>) {
+        let count = Rc::new(Cell::new(0));
+        let mut scheduler = Scheduler::new();
+
+        let counter = count.clone();
+        let id = schedule(&mut scheduler, Box::new(move || counter.set(counter.get() + 1)));
+
+        (scheduler, id, count)
+    }
+
+    #[test]
+    fn one_shots_fire_once_when_due() {
+        let (mut scheduler, id, count) = counting(|scheduler, callback| scheduler.after(1.0, callback));
+
+        scheduler.advance(0.75);
+        assert_eq!(count.get(), 0);
+        assert_eq!(scheduler.remaining(id), Some(0.25));
+
+        scheduler.advance(0.25);
+        assert_eq!(count.get(), 1);
+        assert!(!scheduler.is_active(id));
+
+        scheduler.advance(5.0);
+        assert_eq!(count.get(), 1);
+    }
+
+    #[test]
+    fn repeating_timers_fire_once_per_interval() {
+        let (mut scheduler, id, count) = counting(|scheduler, callback| scheduler.every(0.5, callback));
+
+        scheduler.advance(0.5);
+        assert_eq!(count.get(), 1);
+
+        // a long frame catches up on every interval it covered.
+        scheduler.advance(1.25);
+        assert_eq!(count.get(), 3);
+        assert_eq!(scheduler.remaining(id), Some(0.25));
+        assert!(scheduler.is_active(id));
+    }
+
+    #[test]
+    fn cancelled_timers_never_fire() {
+        let (mut scheduler, id, count) = counting(|scheduler, callback| scheduler.every(0.5, callback));
+
+        scheduler.advance(0.5);
+        scheduler.cancel(id);
+        assert!(!scheduler.is_active(id));
+        assert_eq!(scheduler.remaining(id), None);
+
+        scheduler.advance(2.0);
+        assert_eq!(count.get(), 1);
+    }
+
+    #[test]
+    fn timers_follow_pause_and_time_scale() {
+        let (mut scheduler, id, count) = counting(|scheduler, callback| scheduler.after(1.0, callback));
+        let mut time = time_with_delta(0.5);
+
+        time.set_paused(true);
+        scheduler.update(&time);
+        assert_eq!(scheduler.remaining(id), Some(1.0));
+
+        time.set_paused(false);
+        time.set_time_scale(0.5);
+        scheduler.update(&time);
+        assert_eq!(scheduler.remaining(id), Some(0.75));
+
+        time.set_time_scale(2.0);
+        scheduler.update(&time);
+        assert_eq!(count.get(), 1);
+    }
+}
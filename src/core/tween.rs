@@ -0,0 +1,255 @@
+use std::f32::consts::PI;
+
+use cgmath::{Vector3, VectorSpace};
+
+use super::time::Time;
+
+/// Standard easing curves, mapping progress in 0..1 to eased progress.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Easing {
+    Linear,
+    QuadIn,
+    QuadOut,
+    QuadInOut,
+    CubicIn,
+    CubicOut,
+    CubicInOut,
+    SineIn,
+    SineOut,
+    SineInOut,
+    ExpoIn,
+    ExpoOut,
+    ExpoInOut,
+    /// Pulls back a little before starting.
+    BackIn,
+    /// Overshoots the end a little before settling.
+    BackOut,
+    ElasticOut,
+    BounceOut,
+}
+
+impl Easing {
+    pub fn apply(self, t: f32) -> f32 {
+        const BACK: f32 = 1.70158;
+
+        let t = t.clamp(0.0, 1.0);
+
+        match self {
+            Easing::Linear => t,
+            Easing::QuadIn => t * t,
+            Easing::QuadOut => 1.0 - (1.0 - t) * (1.0 - t),
+            Easing::QuadInOut => {
+                if t < 0.5 { 2.0 * t * t } else { 1.0 - (-2.0 * t + 2.0).powi(2) / 2.0 }
+            }
+            Easing::CubicIn => t * t * t,
+            Easing::CubicOut => 1.0 - (1.0 - t).powi(3),
+            Easing::CubicInOut => {
+                if t < 0.5 { 4.0 * t * t * t } else { 1.0 - (-2.0 * t + 2.0).powi(3) / 2.0 }
+            }
+            Easing::SineIn => 1.0 - (t * PI / 2.0).cos(),
+            Easing::SineOut => (t * PI / 2.0).sin(),
+            Easing::SineInOut => -((t * PI).cos() - 1.0) / 2.0,
+            Easing::ExpoIn => if t == 0.0 { 0.0 } else { 2.0_f32.powf(10.0 * t - 10.0) },
+            Easing::ExpoOut => if t == 1.0 { 1.0 } else { 1.0 - 2.0_f32.powf(-10.0 * t) },
+            Easing::ExpoInOut => {
+                if t == 0.0 || t == 1.0 {
+                    t
+                } else if t < 0.5 {
+                    2.0_f32.powf(20.0 * t - 10.0) / 2.0
+                } else {
+                    (2.0 - 2.0_f32.powf(-20.0 * t + 10.0)) / 2.0
+                }
+            }
+            Easing::BackIn => (BACK + 1.0) * t * t * t - BACK * t * t,
+            Easing::BackOut => 1.0 + (BACK + 1.0) * (t - 1.0).powi(3) + BACK * (t - 1.0).powi(2),
+            Easing::ElasticOut => {
+                if t == 0.0 || t == 1.0 {
+                    t
+                } else {
+                    2.0_f32.powf(-10.0 * t) * ((t * 10.0 - 0.75) * (2.0 * PI / 3.0)).sin() + 1.0
+                }
+            }
+            Easing::BounceOut => {
+                const N: f32 = 7.5625;
+                const D: f32 = 2.75;
+
+                if t < 1.0 / D {
+                    N * t * t
+                } else if t < 2.0 / D {
+                    let t = t - 1.5 / D;
+                    N * t * t + 0.75
+                } else if t < 2.5 / D {
+                    let t = t - 2.25 / D;
+                    N * t * t + 0.9375
+                } else {
+                    let t = t - 2.625 / D;
+                    N * t * t + 0.984375
+                }
+            }
+        }
+    }
+}
+
+/// Linear RGBA color with components in 0..1.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Color {
+    pub r: f32,
+    pub g: f32,
+    pub b: f32,
+    pub a: f32,
+}
+
+impl Color {
+    pub const WHITE: Color = Color::rgb(1.0, 1.0, 1.0);
+    pub const BLACK: Color = Color::rgb(0.0, 0.0, 0.0);
+
+    pub const fn rgb(r: f32, g: f32, b: f32) -> Self {
+        Self { r, g, b, a: 1.0 }
+    }
+
+    pub const fn rgba(r: f32, g: f32, b: f32, a: f32) -> Self {
+        Self { r, g, b, a }
+    }
+}
+
+impl From<Color> for [f32; 4] {
+    fn from(color: Color) -> Self {
+        [color.r, color.g, color.b, color.a]
+    }
+}
+
+/// Values a tween can interpolate.
+pub trait Tweenable: Copy {
+    fn interpolate(self, to: Self, t: f32) -> Self;
+}
+
+impl Tweenable for f32 {
+    fn interpolate(self, to: Self, t: f32) -> Self {
+        self + (to - self) * t
+    }
+}
+
+impl Tweenable for Vector3<f32> {
+    fn interpolate(self, to: Self, t: f32) -> Self {
+        self.lerp(to, t)
+    }
+}
+
+impl Tweenable for Color {
+    fn interpolate(self, to: Self, t: f32) -> Self {
+        Color {
+            r: self.r.interpolate(to.r, t),
+            g: self.g.interpolate(to.g, t),
+            b: self.b.interpolate(to.b, t),
+            a: self.a.interpolate(to.a, t),
+        }
+    }
+}
+
+/// Animates a value from `from` to `to` over `duration` seconds of game time.
+#[derive(Debug, Clone, Copy)]
+pub struct Tween<T: Tweenable> {
+    from: T,
+    to: T,
+    duration: f32,
+    elapsed: f32,
+    easing: Easing,
+    is_looping: bool,
+}
+
+impl<T: Tweenable> Tween<T> {
+    pub fn new(from: T, to: T, duration: f32, easing: Easing) -> Self {
+        Self {
+            from,
+            to,
+            duration,
+            elapsed: 0.0,
+            easing,
+            is_looping: false,
+        }
+    }
+
+    /// Start over from `from` every time the end is reached.
+    pub fn looping(mut self) -> Self {
+        self.is_looping = true;
+        self
+    }
+
+    /// Advance by the game time of the last frame and return the new value.
+    pub fn update(&mut self, time: &Time) -> T {
+        self.advance(time.delta_time())
+    }
+
+    /// Advance by `delta_time` seconds and return the new value.
+    pub fn advance(&mut self, delta_time: f32) -> T {
+        self.elapsed += delta_time;
+
+        if self.is_looping && self.duration > 0.0 {
+            self.elapsed %= self.duration;
+        } else {
+            self.elapsed = self.elapsed.min(self.duration);
+        }
+
+        self.value()
+    }
+
+    pub fn value(&self) -> T {
+        self.from.interpolate(self.to, self.easing.apply(self.progress()))
+    }
+
+    /// Linear progress in 0..1, before easing.
+    pub fn progress(&self) -> f32 {
+        if self.duration > 0.0 { self.elapsed / self.duration } else { 1.0 }
+    }
+
+    /// Never true for a looping tween.
+    pub fn is_finished(&self) -> bool {
+        !self.is_looping && self.elapsed >= self.duration
+    }
+
+    pub fn reset(&mut self) {
+        self.elapsed = 0.0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EASINGS: [Easing; 17] = [
+        Easing::Linear,
+        Easing::QuadIn, Easing::QuadOut, Easing::QuadInOut,
+        Easing::CubicIn, Easing::CubicOut, Easing::CubicInOut,
+        Easing::SineIn, Easing::SineOut, Easing::SineInOut,
+        Easing::ExpoIn, Easing::ExpoOut, Easing::ExpoInOut,
+        Easing::BackIn, Easing::BackOut,
+        Easing::ElasticOut, Easing::BounceOut,
+    ];
+
+    #[test]
+    fn easings_start_at_0_and_end_at_1() {
+        for easing in EASINGS {
+            assert!(easing.apply(0.0).abs() < 1e-5, "{easing:?} at 0 is {}", easing.apply(0.0));
+            assert!((easing.apply(1.0) - 1.0).abs() < 1e-5, "{easing:?} at 1 is {}", easing.apply(1.0));
+
+            // progress outside 0..1 is clamped.
+            assert_eq!(easing.apply(-1.0), easing.apply(0.0), "{easing:?}");
+            assert_eq!(easing.apply(2.0), easing.apply(1.0), "{easing:?}");
+        }
+    }
+
+    #[test]
+    fn tweens_stop_at_the_end_unless_looping() {
+        let mut tween = Tween::new(0.0, 10.0, 2.0, Easing::Linear);
+        assert_eq!(tween.advance(0.5), 2.5);
+        assert_eq!(tween.advance(5.0), 10.0);
+        assert!(tween.is_finished());
+
+        tween.reset();
+        assert_eq!(tween.value(), 0.0);
+
+        let mut looping = Tween::new(0.0, 10.0, 2.0, Easing::Linear).looping();
+        assert_eq!(looping.advance(2.5), 2.5);
+        assert!(!looping.is_finished());
+    }
+}
@@ -0,0 +1,360 @@
+use cgmath::Vector2;
+use std::{cell::RefCell, os::raw::c_void};
+use windows::{
+    core::*, Win32::{Foundation::*, Graphics::Gdi::ClientToScreen, System::LibraryLoader::GetModuleHandleA, UI::WindowsAndMessaging::*},
+};
+
+use super::{event::{Event, MouseButton}, gamepad::Gamepad, input::KeyCode, window::{CursorMode, Window, WindowHandle}};
+
+/// Window state `wndproc` writes into, reached through the window user data.
+struct WindowState {
+    size: Vector2<u32>,
+    is_minimized: bool,
+    is_focused: bool,
+    events: Vec<Event>,
+
+    cursor_mode: CursorMode,
+    last_cursor: Option<Vector2<i32>>,
+}
+
+pub struct Win32Window {
+    pub(crate) hwnd: HWND,
+    pub(crate) instance: HMODULE,
+
+    // boxed so the address handed to `wndproc` stays valid when the window is moved.
+    state: Box<RefCell<WindowState>>,
+
+    gamepad: Gamepad,
+}
+
+impl Win32Window {
+    pub fn new() -> Win32Window {
+        unsafe {
+            let instance = GetModuleHandleA(None).unwrap();
+            debug_assert!(instance.0 != 0);
+
+            let window_class = s!("window");
+            
+            let wc = WNDCLASSA {
+                hCursor: LoadCursorW(None, IDC_ARROW).unwrap(),
+                hInstance: instance.into(),
+                lpszClassName: window_class,
+
+                style: CS_HREDRAW | CS_VREDRAW,
+                lpfnWndProc: Some(wndproc),
+                ..Default::default()
+            };
+            
+            let atom = RegisterClassA(&wc);
+            debug_assert!(atom != 0);
+
+            let window = CreateWindowExA(
+                WS_EX_APPWINDOW,
+                window_class,
+                s!("This is a sample window"),
+                WS_THICKFRAME | WS_CAPTION | WS_SYSMENU | WS_MINIMIZEBOX | WS_MAXIMIZEBOX | WS_OVERLAPPED,
+                CW_USEDEFAULT,
+                CW_USEDEFAULT,
+                1080,
+                720,
+                None,
+                None,
+                instance,
+                None,
+            );
+
+            let state = Box::new(RefCell::new(WindowState {
+                size: Vector2 { x: 1080, y: 720 },
+                is_minimized: false,
+                is_focused: false,
+                events: Vec::new(),
+
+                cursor_mode: CursorMode::Normal,
+                last_cursor: None,
+            }));
+            SetWindowLongPtrA(window, GWLP_USERDATA, &*state as *const RefCell<WindowState> as isize);
+
+            ShowWindow(window, SW_SHOW);
+
+            Self {
+                hwnd:window,
+                instance,
+
+                state,
+
+                gamepad: Gamepad::new(),
+            }
+        }
+    }
+}
+
+impl Window for Win32Window {
+    fn size(&self) -> Vector2<u32> {
+        self.state.borrow().size
+    }
+
+    fn poll_events(&mut self) -> Vec<Event> {
+        unsafe {
+            let mut msg = MSG::default();
+    
+            while PeekMessageA(&mut msg, None, 0, 0, PM_REMOVE).into() {
+                if msg.message == WM_QUIT {
+                    self.state.borrow_mut().events.push(Event::CloseRequested);
+                    break;
+                }
+
+                _ = TranslateMessage(&msg);
+                DispatchMessageA(&msg);
+            }
+        }
+
+        self.recenter_cursor();
+
+        let mut events: Vec<Event> = self.state.borrow_mut().events.drain(..).collect();
+        self.gamepad.poll(&mut events);
+
+        events
+    }
+
+    fn set_cursor_mode(&mut self, mode: CursorMode) {
+        let mut state = self.state.borrow_mut();
+
+        let was_hidden = state.cursor_mode != CursorMode::Normal;
+        let is_hidden = mode != CursorMode::Normal;
+        if was_hidden != is_hidden {
+            unsafe { ShowCursor(!is_hidden) };
+        }
+
+        if mode != CursorMode::Locked {
+            unsafe { _ = ClipCursor(None) };
+        }
+
+        state.cursor_mode = mode;
+        state.last_cursor = None;
+    }
+
+    fn handle(&self) -> WindowHandle {
+        WindowHandle::Win32 { 
+            hinstance: self.instance.0 as *const c_void, 
+            hwnd: self.hwnd.0 as *const c_void 
+        }
+    }
+}
+
+impl Drop for Win32Window {
+    fn drop(&mut self) {
+        self.set_cursor_mode(CursorMode::Normal);
+
+        unsafe {
+            // messages sent while destroying the window must not reach the state freed after this.
+            SetWindowLongPtrA(self.hwnd, GWLP_USERDATA, 0);
+            _ = DestroyWindow(self.hwnd);
+        }
+    }
+}
+
+impl Win32Window {
+    /// Keep a locked cursor confined and back at the window center, so the next
+    /// frame measures its motion from there.
+    fn recenter_cursor(&self) {
+        let state = self.state.borrow();
+        if state.cursor_mode != CursorMode::Locked || !state.is_focused {
+            return;
+        }
+
+        unsafe {
+            let mut rect = RECT::default();
+            if GetClientRect(self.hwnd, &mut rect).is_err() {
+                return;
+            }
+
+            let mut top_left = POINT { x: rect.left, y: rect.top };
+            let mut bottom_right = POINT { x: rect.right, y: rect.bottom };
+            _ = ClientToScreen(self.hwnd, &mut top_left);
+            _ = ClientToScreen(self.hwnd, &mut bottom_right);
+
+            let screen_rect = RECT { 
+                left: top_left.x, 
+                top: top_left.y, 
+                right: bottom_right.x, 
+                bottom: bottom_right.y 
+            };
+            _ = ClipCursor(Some(&screen_rect));
+
+            let center = state.center();
+            _ = SetCursorPos(top_left.x + center.x, top_left.y + center.y);
+        }
+    }
+}
+
+impl WindowState {
+    fn center(&self) -> Vector2<i32> {
+        Vector2 { x: self.size.x as i32 / 2, y: self.size.y as i32 / 2 }
+    }
+
+    fn translate(&mut self, message: u32, wparam: WPARAM, lparam: LPARAM) {
+        let event = match message {
+            WM_SIZE => {
+                if wparam.0 as u32 == SIZE_MINIMIZED {
+                    self.is_minimized = true;
+                    Some(Event::Minimized(true))
+                } else {
+                    if self.is_minimized {
+                        self.is_minimized = false;
+                        self.events.push(Event::Minimized(false));
+                    }
+
+                    let size = Vector2 { 
+                        x: low_word(lparam.0) as u32, 
+                        y: high_word(lparam.0) as u32 
+                    };
+                    if size != self.size {
+                        self.size = size;
+                        Some(Event::Resized(size))
+                    } else {
+                        None
+                    }
+                }
+            }
+            WM_MOVE => Some(Event::Moved(signed_point(lparam))),
+            WM_SETFOCUS => {
+                self.is_focused = true;
+                Some(Event::Focused(true))
+            }
+            WM_KILLFOCUS => {
+                self.is_focused = false;
+                // give the cursor back to the other windows.
+                unsafe { _ = ClipCursor(None) };
+                Some(Event::Focused(false))
+            }
+            WM_DPICHANGED => Some(Event::ScaleFactorChanged(
+                low_word(wparam.0 as isize) as f32 / USER_DEFAULT_SCREEN_DPI as f32
+            )),
+
+            WM_KEYDOWN | WM_SYSKEYDOWN => Some(Event::KeyDown(key_code(wparam, lparam))),
+            WM_KEYUP | WM_SYSKEYUP => Some(Event::KeyUp(key_code(wparam, lparam))),
+            WM_CHAR => char::from_u32(wparam.0 as u32).map(Event::Char),
+
+            WM_MOUSEMOVE => {
+                let position = signed_point(lparam);
+
+                if self.cursor_mode == CursorMode::Locked {
+                    // the cursor is put back at the center every frame, moving there is not motion.
+                    let center = self.center();
+                    if position != center {
+                        self.events.push(Event::MouseMotion((position - center).cast().unwrap()));
+                    }
+                    None
+                } else {
+                    if let Some(last_cursor) = self.last_cursor {
+                        self.events.push(Event::MouseMotion((position - last_cursor).cast().unwrap()));
+                    }
+                    self.last_cursor = Some(position);
+
+                    Some(Event::MouseMoved(position))
+                }
+            }
+            WM_LBUTTONDOWN => Some(Event::MouseDown(MouseButton::Left)),
+            WM_LBUTTONUP => Some(Event::MouseUp(MouseButton::Left)),
+            WM_RBUTTONDOWN => Some(Event::MouseDown(MouseButton::Right)),
+            WM_RBUTTONUP => Some(Event::MouseUp(MouseButton::Right)),
+            WM_MBUTTONDOWN => Some(Event::MouseDown(MouseButton::Middle)),
+            WM_MBUTTONUP => Some(Event::MouseUp(MouseButton::Middle)),
+            WM_XBUTTONDOWN => Some(Event::MouseDown(MouseButton::Other(high_word(wparam.0 as isize) as u8))),
+            WM_XBUTTONUP => Some(Event::MouseUp(MouseButton::Other(high_word(wparam.0 as isize) as u8))),
+            WM_MOUSEWHEEL => Some(Event::MouseWheel(
+                high_word(wparam.0 as isize) as i16 as f32 / WHEEL_DELTA as f32
+            )),
+            _ => None,
+        };
+
+        if let Some(event) = event {
+            self.events.push(event);
+        }
+    }
+}
+
+const USER_DEFAULT_SCREEN_DPI: u32 = 96;
+
+/// Translate a virtual key, shift, control and alt sides are told apart from the key data.
+fn key_code(wparam: WPARAM, lparam: LPARAM) -> KeyCode {
+    let scan_code = (lparam.0 >> 16) & 0xff;
+    let is_extended = (lparam.0 >> 24) & 1 == 1;
+
+    match wparam.0 as u32 {
+        key @ 0x41..=0x5A => KeyCode::letter(key - 0x41),
+        key @ 0x30..=0x39 => KeyCode::digit(key - 0x30),
+        key @ 0x70..=0x7B => KeyCode::function(key - 0x70),
+        0x1B => KeyCode::Escape,
+        0x0D => KeyCode::Enter,
+        0x20 => KeyCode::Space,
+        0x09 => KeyCode::Tab,
+        0x08 => KeyCode::Backspace,
+        0x2D => KeyCode::Insert,
+        0x2E => KeyCode::Delete,
+        0x24 => KeyCode::Home,
+        0x23 => KeyCode::End,
+        0x21 => KeyCode::PageUp,
+        0x22 => KeyCode::PageDown,
+        0x25 => KeyCode::Left,
+        0x27 => KeyCode::Right,
+        0x26 => KeyCode::Up,
+        0x28 => KeyCode::Down,
+        // VK_SHIFT, the right shift key has its own scan code.
+        0x10 if scan_code == 0x36 => KeyCode::RightShift,
+        0x10 => KeyCode::LeftShift,
+        // VK_CONTROL and VK_MENU, the right keys are flagged as extended.
+        0x11 if is_extended => KeyCode::RightControl,
+        0x11 => KeyCode::LeftControl,
+        0x12 if is_extended => KeyCode::RightAlt,
+        0x12 => KeyCode::LeftAlt,
+        _ => KeyCode::Unknown,
+    }
+}
+
+fn low_word(value: isize) -> u16 {
+    (value & 0xffff) as u16
+}
+
+fn high_word(value: isize) -> u16 {
+    ((value >> 16) & 0xffff) as u16
+}
+
+/// Signed client coordinates packed in a LPARAM, they are negative left or above the window.
+fn signed_point(lparam: LPARAM) -> Vector2<i32> {
+    Vector2 { 
+        x: low_word(lparam.0) as i16 as i32, 
+        y: high_word(lparam.0) as i16 as i32 
+    }
+}
+
+extern "system" fn wndproc(window: HWND, message: u32, wparam: WPARAM, lparam: LPARAM) -> LRESULT {
+    // messages sent while the window is being created arrive before the state is attached.
+    let state = unsafe {
+        (GetWindowLongPtrA(window, GWLP_USERDATA) as *const RefCell<WindowState>).as_ref()
+    };
+
+    match message {
+        WM_CREATE => {
+            println!("Window created");
+            LRESULT::default()
+        }
+        WM_CLOSE => {
+            // the application decides when to quit, so the window is not destroyed here.
+            if let Some(state) = state {
+                state.borrow_mut().events.push(Event::CloseRequested);
+            }
+            LRESULT::default()
+        }
+        WM_DESTROY => {
+            unsafe { PostQuitMessage(0) };
+            LRESULT::default()
+        }
+        _ => {
+            if let Some(state) = state {
+                state.borrow_mut().translate(message, wparam, lparam);
+            }
+            unsafe { DefWindowProcA(window, message, wparam, lparam) }
+        }
+    }
+}
@@ -0,0 +1,49 @@
+use std::{ffi::CStr, os::raw::{c_ulong, c_void}};
+
+use ash::extensions::khr;
+use cgmath::Vector2;
+
+use super::event::Event;
+
+#[cfg(windows)]
+pub type PlatformWindow = super::win32::Win32Window;
+#[cfg(target_os = "linux")]
+pub type PlatformWindow = super::xlib::XlibWindow;
+
+/// Native handles a Vulkan surface can be created from.
+pub enum WindowHandle {
+    Win32 { hinstance: *const c_void, hwnd: *const c_void },
+    Xlib { display: *mut c_void, window: c_ulong },
+    Wayland { display: *mut c_void, surface: *mut c_void },
+}
+
+impl WindowHandle {
+    /// Instance extension needed to create a surface for this kind of window.
+    pub fn surface_extension_name(&self) -> &'static CStr {
+        match self {
+            Self::Win32 { .. } => khr::Win32Surface::name(),
+            Self::Xlib { .. } => khr::XlibSurface::name(),
+            Self::Wayland { .. } => khr::WaylandSurface::name(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CursorMode {
+    Normal,
+    Hidden,
+    /// Hidden and held inside the window, only relative motion is reported.
+    Locked,
+}
+
+pub trait Window {
+    /// Inner size of the window in pixels.
+    fn size(&self) -> Vector2<u32>;
+
+    /// Drain every pending window message, translated into engine events.
+    fn poll_events(&mut self) -> Vec<Event>;
+
+    fn set_cursor_mode(&mut self, mode: CursorMode);
+
+    fn handle(&self) -> WindowHandle;
+}
@@ -0,0 +1,332 @@
+use cgmath::Vector2;
+use std::{ffi::CString, mem::MaybeUninit, os::raw::{c_char, c_long, c_ulong, c_void}, ptr};
+use x11::{keysym, xlib};
+
+use super::{event::{Event, MouseButton}, gamepad::Gamepad, input::KeyCode, window::{CursorMode, Window, WindowHandle}};
+
+pub struct XlibWindow {
+    pub(crate) display: *mut xlib::Display,
+    pub(crate) window: xlib::Window,
+    wm_delete_window: xlib::Atom,
+
+    pub(crate) size: Vector2<u32>,
+    position: Vector2<i32>,
+
+    is_focused: bool,
+    cursor_mode: CursorMode,
+    last_cursor: Option<Vector2<i32>>,
+    blank_cursor: xlib::Cursor,
+
+    gamepad: Gamepad,
+}
+
+impl XlibWindow {
+    pub fn new() -> XlibWindow {
+        unsafe {
+            let display = xlib::XOpenDisplay(ptr::null());
+            if display.is_null() {
+                panic!("Failed to open X display!");
+            }
+
+            let screen = xlib::XDefaultScreen(display);
+            let root = xlib::XRootWindow(display, screen);
+            let black = xlib::XBlackPixel(display, screen);
+
+            let window = xlib::XCreateSimpleWindow(
+                display, 
+                root, 
+                0, 
+                0, 
+                1080, 
+                720, 
+                0, 
+                black, 
+                black
+            );
+
+            xlib::XSelectInput(
+                display, 
+                window, 
+                xlib::KeyPressMask 
+                    | xlib::KeyReleaseMask 
+                    | xlib::ButtonPressMask 
+                    | xlib::ButtonReleaseMask 
+                    | xlib::PointerMotionMask 
+                    | xlib::FocusChangeMask 
+                    | xlib::StructureNotifyMask
+            );
+
+            let title = CString::new("This is a sample window").unwrap();
+            xlib::XStoreName(display, window, title.as_ptr());
+
+            // ask the window manager to send a message instead of killing the connection on close.
+            let protocol_name = CString::new("WM_DELETE_WINDOW").unwrap();
+            let mut wm_delete_window = xlib::XInternAtom(display, protocol_name.as_ptr(), xlib::False);
+            xlib::XSetWMProtocols(display, window, &mut wm_delete_window, 1);
+
+            // report held keys as a single press instead of release and press pairs.
+            xlib::XkbSetDetectableAutoRepeat(display, xlib::True, ptr::null_mut());
+
+            // X has no call to hide the cursor, an empty 1x1 pixmap cursor stands in for it.
+            let empty = [0 as c_char; 1];
+            let pixmap = xlib::XCreateBitmapFromData(display, window, empty.as_ptr(), 1, 1);
+            let mut color = MaybeUninit::<xlib::XColor>::zeroed().assume_init();
+            let blank_cursor = xlib::XCreatePixmapCursor(display, pixmap, pixmap, &mut color, &mut color, 0, 0);
+            xlib::XFreePixmap(display, pixmap);
+
+            xlib::XMapWindow(display, window);
+            xlib::XFlush(display);
+
+            Self {
+                display,
+                window,
+                wm_delete_window,
+
+                size: Vector2 { x: 1080, y: 720 },
+                position: Vector2 { x: 0, y: 0 },
+
+                is_focused: false,
+                cursor_mode: CursorMode::Normal,
+                last_cursor: None,
+                blank_cursor,
+
+                gamepad: Gamepad::new(),
+            }
+        }
+    }
+
+    fn key_code(keysym: c_ulong) -> KeyCode {
+        match keysym as u32 {
+            keysym @ keysym::XK_a..=keysym::XK_z => KeyCode::letter(keysym - keysym::XK_a),
+            keysym @ keysym::XK_A..=keysym::XK_Z => KeyCode::letter(keysym - keysym::XK_A),
+            keysym @ keysym::XK_0..=keysym::XK_9 => KeyCode::digit(keysym - keysym::XK_0),
+            keysym @ keysym::XK_F1..=keysym::XK_F12 => KeyCode::function(keysym - keysym::XK_F1),
+            keysym::XK_Escape => KeyCode::Escape,
+            keysym::XK_Return => KeyCode::Enter,
+            keysym::XK_space => KeyCode::Space,
+            keysym::XK_Tab => KeyCode::Tab,
+            keysym::XK_BackSpace => KeyCode::Backspace,
+            keysym::XK_Insert => KeyCode::Insert,
+            keysym::XK_Delete => KeyCode::Delete,
+            keysym::XK_Home => KeyCode::Home,
+            keysym::XK_End => KeyCode::End,
+            keysym::XK_Page_Up => KeyCode::PageUp,
+            keysym::XK_Page_Down => KeyCode::PageDown,
+            keysym::XK_Left => KeyCode::Left,
+            keysym::XK_Right => KeyCode::Right,
+            keysym::XK_Up => KeyCode::Up,
+            keysym::XK_Down => KeyCode::Down,
+            keysym::XK_Shift_L => KeyCode::LeftShift,
+            keysym::XK_Shift_R => KeyCode::RightShift,
+            keysym::XK_Control_L => KeyCode::LeftControl,
+            keysym::XK_Control_R => KeyCode::RightControl,
+            keysym::XK_Alt_L => KeyCode::LeftAlt,
+            keysym::XK_Alt_R => KeyCode::RightAlt,
+            _ => KeyCode::Unknown,
+        }
+    }
+
+    fn mouse_button(button: u32) -> MouseButton {
+        match button {
+            xlib::Button1 => MouseButton::Left,
+            xlib::Button2 => MouseButton::Middle,
+            xlib::Button3 => MouseButton::Right,
+            // buttons 4 to 7 are the wheels, 8 and 9 are back and forward.
+            _ => MouseButton::Other(button.saturating_sub(7) as u8),
+        }
+    }
+
+    fn center(&self) -> Vector2<i32> {
+        Vector2 { x: self.size.x as i32 / 2, y: self.size.y as i32 / 2 }
+    }
+
+    fn grab_pointer(&self) {
+        unsafe {
+            xlib::XGrabPointer(
+                self.display, 
+                self.window, 
+                xlib::True, 
+                (xlib::ButtonPressMask | xlib::ButtonReleaseMask | xlib::PointerMotionMask) as u32, 
+                xlib::GrabModeAsync, 
+                xlib::GrabModeAsync, 
+                self.window, 
+                self.blank_cursor, 
+                xlib::CurrentTime
+            );
+        }
+    }
+
+    /// Put a locked cursor back at the window center, so the next frame measures its motion from there.
+    fn recenter_cursor(&self) {
+        if self.cursor_mode != CursorMode::Locked || !self.is_focused {
+            return;
+        }
+
+        let center = self.center();
+        unsafe {
+            xlib::XWarpPointer(self.display, 0, self.window, 0, 0, 0, 0, center.x, center.y);
+            xlib::XFlush(self.display);
+        }
+    }
+
+    fn translate(&mut self, event: &mut xlib::XEvent, events: &mut Vec<Event>) {
+        unsafe {
+            match event.get_type() {
+                xlib::ClientMessage => {
+                    let message = xlib::XClientMessageEvent::from(*event);
+                    if message.data.get_long(0) as c_long == self.wm_delete_window as c_long {
+                        events.push(Event::CloseRequested);
+                    }
+                }
+                xlib::DestroyNotify => events.push(Event::CloseRequested),
+                xlib::ConfigureNotify => {
+                    let configure = event.configure;
+
+                    let size = Vector2 { x: configure.width as u32, y: configure.height as u32 };
+                    if size != self.size {
+                        self.size = size;
+                        events.push(Event::Resized(size));
+                    }
+
+                    let position = Vector2 { x: configure.x, y: configure.y };
+                    if position != self.position {
+                        self.position = position;
+                        events.push(Event::Moved(position));
+                    }
+                }
+                // the window manager unmaps iconified windows.
+                xlib::UnmapNotify => events.push(Event::Minimized(true)),
+                xlib::MapNotify => events.push(Event::Minimized(false)),
+                xlib::FocusIn => {
+                    self.is_focused = true;
+                    // the grab is lost with the focus, take it again when coming back.
+                    if self.cursor_mode == CursorMode::Locked {
+                        self.grab_pointer();
+                    }
+                    events.push(Event::Focused(true));
+                }
+                xlib::FocusOut => {
+                    self.is_focused = false;
+                    events.push(Event::Focused(false));
+                }
+
+                xlib::KeyPress => {
+                    let keysym = xlib::XLookupKeysym(&mut event.key, 0);
+                    events.push(Event::KeyDown(Self::key_code(keysym)));
+
+                    let mut text = [0 as c_char; 8];
+                    let count = xlib::XLookupString(
+                        &mut event.key, 
+                        text.as_mut_ptr(), 
+                        text.len() as i32, 
+                        ptr::null_mut(), 
+                        ptr::null_mut()
+                    );
+                    // the looked up text is latin-1, which maps one to one onto unicode.
+                    events.extend(
+                        text[..count.max(0) as usize].iter().map(|&c| Event::Char(char::from(c as u8)))
+                    );
+                }
+                xlib::KeyRelease => {
+                    let keysym = xlib::XLookupKeysym(&mut event.key, 0);
+                    events.push(Event::KeyUp(Self::key_code(keysym)));
+                }
+
+                xlib::MotionNotify => {
+                    let motion = event.motion;
+                    let position = Vector2 { x: motion.x, y: motion.y };
+
+                    if self.cursor_mode == CursorMode::Locked {
+                        // the warp back to the center is reported as motion too, skip it.
+                        let center = self.center();
+                        if position != center {
+                            events.push(Event::MouseMotion((position - center).cast().unwrap()));
+                        }
+                    } else {
+                        if let Some(last_cursor) = self.last_cursor {
+                            events.push(Event::MouseMotion((position - last_cursor).cast().unwrap()));
+                        }
+                        self.last_cursor = Some(position);
+
+                        events.push(Event::MouseMoved(position));
+                    }
+                }
+                // X reports the wheel as buttons 4 and 5.
+                xlib::ButtonPress => match event.button.button {
+                    xlib::Button4 => events.push(Event::MouseWheel(1.0)),
+                    xlib::Button5 => events.push(Event::MouseWheel(-1.0)),
+                    // horizontal scrolling is not reported.
+                    6 | 7 => (),
+                    button => events.push(Event::MouseDown(Self::mouse_button(button))),
+                },
+                xlib::ButtonRelease => match event.button.button {
+                    xlib::Button4 | xlib::Button5 | 6 | 7 => (),
+                    button => events.push(Event::MouseUp(Self::mouse_button(button))),
+                },
+                _ => (),
+            }
+        }
+    }
+}
+
+impl Drop for XlibWindow {
+    fn drop(&mut self) {
+        unsafe {
+            xlib::XFreeCursor(self.display, self.blank_cursor);
+            xlib::XDestroyWindow(self.display, self.window);
+            xlib::XCloseDisplay(self.display);
+        }
+    }
+}
+
+impl Window for XlibWindow {
+    fn size(&self) -> Vector2<u32> {
+        self.size
+    }
+
+    fn poll_events(&mut self) -> Vec<Event> {
+        let mut events = Vec::new();
+
+        unsafe {
+            while xlib::XPending(self.display) > 0 {
+                let mut event = MaybeUninit::<xlib::XEvent>::uninit();
+                xlib::XNextEvent(self.display, event.as_mut_ptr());
+                let mut event = event.assume_init();
+
+                self.translate(&mut event, &mut events);
+            }
+        }
+
+        self.recenter_cursor();
+        self.gamepad.poll(&mut events);
+
+        events
+    }
+
+    fn set_cursor_mode(&mut self, mode: CursorMode) {
+        unsafe {
+            match mode {
+                CursorMode::Normal => xlib::XUndefineCursor(self.display, self.window),
+                CursorMode::Hidden | CursorMode::Locked => xlib::XDefineCursor(self.display, self.window, self.blank_cursor),
+            };
+
+            if mode == CursorMode::Locked {
+                self.grab_pointer();
+            } else {
+                xlib::XUngrabPointer(self.display, xlib::CurrentTime);
+            }
+
+            xlib::XFlush(self.display);
+        }
+
+        self.cursor_mode = mode;
+        self.last_cursor = None;
+    }
+
+    fn handle(&self) -> WindowHandle {
+        WindowHandle::Xlib { 
+            display: self.display as *mut c_void, 
+            window: self.window 
+        }
+    }
+}
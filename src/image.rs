@@ -1,4 +1,4 @@
-use std::{cmp::max, path::Path, ptr, rc::Rc};
+use std::{cmp::max, io, path::Path, ptr, rc::Rc};
 
 use ash::vk;
 
@@ -17,8 +17,14 @@ pub struct Image {
 }
 
 impl Image {
-    pub fn new(device: Rc<GraphicDevice>, command_pool: &CommandPool, image_path: &Path) -> Self {
-        let mut image_object = image::open(image_path).unwrap(); // this function is slow in debug mode.
+    pub fn new(device: Rc<GraphicDevice>, command_pool: &CommandPool, image_path: &Path) -> io::Result<Self> {
+        let invalid = |reason: String| io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("{}: {reason}", image_path.display())
+        );
+
+        let mut image_object = image::open(image_path) // this function is slow in debug mode.
+            .map_err(|error| invalid(error.to_string()))?;
         image_object = image_object.flipv();
         let (image_width, image_height) = (image_object.width(), image_object.height());
         let image_data = match &image_object {
@@ -26,7 +32,7 @@ impl Image {
             | image::DynamicImage::ImageRgb8(_) => image_object.to_rgba8().into_raw(),
             | image::DynamicImage::ImageLumaA8(_)
             | image::DynamicImage::ImageRgba8(_) => image_object.to_rgba8().into_raw(),
-            _ => panic!("unsupported file type")
+            _ => return Err(invalid("unsupported pixel format".to_string()))
         };
         let image_size =
             (::std::mem::size_of::<u8>() as u32 * image_width * image_height * 4) as vk::DeviceSize;
@@ -36,7 +42,7 @@ impl Image {
             + 1;
 
         if image_size <= 0 {
-            panic!("Failed to load texture image!")
+            return Err(invalid("empty image".to_string()));
         }
 
         let staging_buffer = Buffer::staging(device.clone(), image_size);
@@ -91,14 +97,14 @@ impl Image {
             Self::create_texture_image_view(&device.logical, texture_image, 1);
         let texture_sampler = Self::create_texture_sampler(&device.logical, mip_levels);
 
-        Self {
+        Ok(Self {
             device,
             image: texture_image,
             memory: texture_image_memory,
             view: texture_image_view,
             sampler: texture_sampler,
             mip_levels
-        }
+        })
     }
 
     pub(crate) fn create_image(
@@ -1,5 +1,5 @@
 pub mod app;
-mod core;
+pub mod core;
 mod renderer;
 mod image;
 mod mesh;
\ No newline at end of file
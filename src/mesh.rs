@@ -1,10 +1,12 @@
-use std::{mem::size_of, path::Path, rc::Rc};
+use std::{io, mem::size_of, path::Path, rc::Rc};
 
 use ash::vk;
 use memoffset::offset_of;
 use tobj::LoadOptions;
 
-use crate::{core::device::GraphicDevice, renderer::{buffer::Buffer, commandpool::CommandPool}};
+use cgmath::Vector3;
+
+use crate::{core::{bounds::{Aabb, BoundingSphere, Ray}, device::GraphicDevice}, renderer::{buffer::Buffer, commandpool::CommandPool}};
 
 #[repr(C)]
 #[derive(Debug, Clone, Copy)]
@@ -54,6 +56,29 @@ pub struct Mesh {
     pub(crate) index_buffer: Buffer,
 
     pub(crate) index_count: u32,
+
+    /// Bounds of the vertices in model space.
+    pub(crate) bounds: Aabb,
+    pub(crate) bounding_sphere: BoundingSphere,
+
+    /// CPU copy of the triangles for picking, only kept when asked for.
+    geometry: Option<Geometry>,
+}
+
+/// Model space triangles of a mesh, three indices per triangle.
+pub(crate) struct Geometry {
+    pub(crate) positions: Vec<Vector3<f32>>,
+    pub(crate) indices: Vec<u32>,
+}
+
+/// Where a ray crosses a mesh.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct MeshHit {
+    /// Ray parameter of the hit.
+    pub(crate) t: f32,
+    pub(crate) triangle: usize,
+    /// Weights of the three triangle corners at the hit.
+    pub(crate) barycentrics: Vector3<f32>,
 }
 
 impl Mesh {
@@ -61,13 +86,37 @@ impl Mesh {
         device: Rc<GraphicDevice>, 
         command_pool: &CommandPool, 
         model_path: &Path
-    ) -> Self {
+    ) -> io::Result<Self> {
+        Self::load(device, command_pool, model_path, false)
+    }
+
+    /// Like `from_obj`, but keeps the positions and indices after the upload so rays can be tested
+    /// against the triangles.
+    pub fn from_obj_with_geometry( 
+        device: Rc<GraphicDevice>, 
+        command_pool: &CommandPool, 
+        model_path: &Path
+    ) -> io::Result<Self> {
+        Self::load(device, command_pool, model_path, true)
+    }
+
+    fn load( 
+        device: Rc<GraphicDevice>, 
+        command_pool: &CommandPool, 
+        model_path: &Path,
+        keep_geometry: bool
+    ) -> io::Result<Self> {
+        let invalid = |reason: String| io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("{}: {reason}", model_path.display())
+        );
+
         let model_obj = tobj::load_obj(
             model_path, &LoadOptions{
                 single_index: true,
                 ..Default::default()
             }
-        ).expect("Failed to load model object!");
+        ).map_err(|error| invalid(error.to_string()))?;
 
         let mut vertices = vec![];
         let mut indices = vec![];
@@ -77,7 +126,7 @@ impl Mesh {
             let mesh = &m.mesh;
 
             if mesh.texcoords.len() == 0 {
-                panic!("Missing texture coordinate for the model.")
+                return Err(invalid("missing texture coordinates".to_string()));
             }
 
             let total_vertices_count = mesh.positions.len() / 3;
@@ -96,7 +145,15 @@ impl Mesh {
 
             indices = mesh.indices.clone();
         }
+
+        if vertices.is_empty() || indices.is_empty() {
+            return Err(invalid("no triangles".to_string()));
+        }
         
+        let positions: Vec<Vector3<f32>> = vertices.iter().map(|vertex| Vector3::from(vertex.pos)).collect();
+        let bounds = Aabb::from_points(positions.iter().copied());
+        let bounding_sphere = BoundingSphere::from_points(&positions);
+
         //VERTEX BUFFER
         let vertex_size = (size_of::<Vertex>() * vertices.len()) as u64;
 
@@ -127,14 +184,38 @@ impl Mesh {
         
         index_staging_buffer.destroy();
         
-        Self {
+        Ok(Self {
             device,
 
             vertex_buffer,
             index_buffer,
 
             index_count: indices.len() as u32,
-        }
+
+            bounds,
+            bounding_sphere,
+
+            geometry: keep_geometry.then_some(Geometry { positions, indices }),
+        })
+    }
+
+    pub(crate) fn has_geometry(&self) -> bool {
+        self.geometry.is_some()
+    }
+
+    /// Closest triangle the model space `ray` crosses, `None` as well when no geometry was kept.
+    pub(crate) fn raycast(&self, ray: &Ray) -> Option<MeshHit> {
+        let geometry = self.geometry.as_ref()?;
+        ray.intersect_aabb(&self.bounds)?;
+
+        geometry.indices.chunks_exact(3).enumerate()
+            .filter_map(|(triangle, corners)| {
+                let corner = |i: usize| geometry.positions[corners[i] as usize];
+                let (t, barycentrics) = ray.intersect_triangle(corner(0), corner(1), corner(2))?;
+
+                Some(MeshHit { t, triangle, barycentrics })
+            })
+            .min_by(|a, b| a.t.total_cmp(&b.t))
     }
 
     pub(crate) fn bind(&self, command_buffer: vk::CommandBuffer) {
@@ -157,7 +238,8 @@ impl Mesh {
         }
     }
 
-    pub(crate) fn draw(&self, command_buffer: vk::CommandBuffer, count: u32) {
+    /// Draw `count` instances, numbered from `first_instance` in the shader.
+    pub(crate) fn draw(&self, command_buffer: vk::CommandBuffer, count: u32, first_instance: u32) {
         unsafe {
             self.device.logical.cmd_draw_indexed(
                 command_buffer, 
@@ -165,7 +247,7 @@ impl Mesh {
                 count, 
                 0, 
                 0, 
-                0
+                first_instance
             );
         }
     }
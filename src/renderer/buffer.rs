@@ -1,4 +1,4 @@
-use std::{ptr, rc::Rc};
+use std::{ptr, rc::Rc, slice};
 
 use ash::vk;
 
@@ -79,6 +79,15 @@ impl Buffer {
         )
     }
 
+    pub fn readback(device: Rc<GraphicDevice>, size: u64) -> Self {
+        Self::new(
+            device, 
+            size, 
+            vk::BufferUsageFlags::TRANSFER_DST, 
+            vk::MemoryPropertyFlags::HOST_VISIBLE | vk::MemoryPropertyFlags::HOST_COHERENT
+        )
+    }
+
     pub fn vertex(device: Rc<GraphicDevice>, size: u64) -> Self {
         Self::new(
             device, 
@@ -131,6 +140,25 @@ impl Buffer {
         }
     }
 
+    /// Copy `count` elements out of a host visible buffer.
+    pub(crate) fn read<T: Copy>(&self, count: usize) -> Vec<T> {
+        unsafe {
+            let data_ptr = self.device.logical
+                .map_memory(
+                    self.memory,
+                    0,
+                    vk::WHOLE_SIZE,
+                    vk::MemoryMapFlags::empty(),
+                )
+                .expect("Failed to Map Memory") as *const T;
+
+            let data = slice::from_raw_parts(data_ptr, count).to_vec();
+            self.device.logical.unmap_memory(self.memory);
+
+            data
+        }
+    }
+
     pub(crate) fn copy(&self, src: &Buffer, command_pool: &CommandPool, size: u64) {
         let command_buffer = command_pool.begin_single_time_command();
 
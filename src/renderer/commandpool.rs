@@ -1,6 +1,6 @@
 use std::{ptr, rc::Rc};
 
-use ash::vk::{self, Framebuffer};
+use ash::vk;
 
 use crate::core::device::GraphicDevice;
 
@@ -16,7 +16,8 @@ impl CommandPool {
         let command_pool_create_info = vk::CommandPoolCreateInfo {
             s_type: vk::StructureType::COMMAND_POOL_CREATE_INFO,
             p_next: ptr::null(),
-            flags: vk::CommandPoolCreateFlags::empty(),
+            // the frame buffers are reset and recorded again every frame.
+            flags: vk::CommandPoolCreateFlags::RESET_COMMAND_BUFFER,
             queue_family_index: device.family_indices.graphics_family.unwrap(),
         };
 
@@ -30,11 +31,12 @@ impl CommandPool {
         Self {device, pool: command_pool, buffers: Vec::new()}
     }
     
-    pub(crate) fn allocate_buffers(&mut self, framebuffers: &Vec<Framebuffer>) {
+    /// Allocate one command buffer per frame in flight.
+    pub(crate) fn allocate_buffers(&mut self, count: usize) {
         let command_buffer_allocate_info = vk::CommandBufferAllocateInfo {
             s_type: vk::StructureType::COMMAND_BUFFER_ALLOCATE_INFO,
             p_next: ptr::null(),
-            command_buffer_count: framebuffers.len() as u32,
+            command_buffer_count: count as u32,
             command_pool: self.pool,
             level: vk::CommandBufferLevel::PRIMARY,
         };
@@ -48,15 +50,19 @@ impl CommandPool {
         self.buffers = command_buffers;
     }
 
+    /// Begin recording `command_buffer` from scratch, for a single submit.
     pub(crate) fn begin_command_buffer(&self, command_buffer: vk::CommandBuffer) {
         let command_buffer_begin_info = vk::CommandBufferBeginInfo {
             s_type: vk::StructureType::COMMAND_BUFFER_BEGIN_INFO,
             p_next: ptr::null(),
             p_inheritance_info: ptr::null(),
-            flags: vk::CommandBufferUsageFlags::SIMULTANEOUS_USE,
+            flags: vk::CommandBufferUsageFlags::ONE_TIME_SUBMIT,
         };
 
         unsafe {
+            self.device.logical
+                .reset_command_buffer(command_buffer, vk::CommandBufferResetFlags::empty())
+                .expect("Failed to reset Command Buffer!");
             self.device.logical
                 .begin_command_buffer(command_buffer, &command_buffer_begin_info)
                 .expect("Failed to begin recording Command Buffer at beginning!");
@@ -196,10 +202,6 @@ impl CommandPool {
         self.buffers = command_buffers;
     }
     */
-    pub(crate) fn get_buffer(&self, i: usize) -> *const vk::CommandBuffer {
-        &self.buffers[i] as *const vk::CommandBuffer
-    }
-
     pub(crate) fn begin_single_time_command(&self) -> vk::CommandBuffer {
         let command_buffer_allocate_info = vk::CommandBufferAllocateInfo {
             s_type: vk::StructureType::COMMAND_BUFFER_ALLOCATE_INFO,
@@ -263,13 +265,6 @@ impl CommandPool {
         }
     }
 
-    pub(crate) fn free_buffers(&self) {
-        unsafe {
-            self.device.logical
-                .free_command_buffers(self.pool, &self.buffers);
-        }
-    }
-
     pub(crate) fn destroy(&self) {
         unsafe {
             self.device.logical.destroy_command_pool(self.pool, None);
@@ -1,6 +1,6 @@
 use std::rc::Rc;
 
-use ash::vk;
+use ash::{prelude::VkResult, vk};
 
 use crate::core::device::GraphicDevice;
 
@@ -8,15 +8,29 @@ pub struct DescriptorPool {
     device: Rc<GraphicDevice>,
     
     pool: vk::DescriptorPool,
-    pub(crate) sets: Vec<vk::DescriptorSet>
 }
 
 impl DescriptorPool {
-    pub fn new(device: Rc<GraphicDevice>, pool_sizes: Vec<vk::DescriptorPoolSize>) -> Self {
+    pub fn new(device: Rc<GraphicDevice>, pool_sizes: Vec<vk::DescriptorPoolSize>, max_sets: u32) -> Self {
+        Self::create(device, pool_sizes, max_sets, vk::DescriptorPoolCreateFlags::empty())
+    }
+
+    /// Pool to `allocate` up to `max_sets` sets from one at a time and `free` them again.
+    pub fn freeable(device: Rc<GraphicDevice>, pool_sizes: Vec<vk::DescriptorPoolSize>, max_sets: u32) -> Self {
+        Self::create(device, pool_sizes, max_sets, vk::DescriptorPoolCreateFlags::FREE_DESCRIPTOR_SET)
+    }
+
+    fn create(
+        device: Rc<GraphicDevice>, 
+        pool_sizes: Vec<vk::DescriptorPoolSize>, 
+        max_sets: u32, 
+        flags: vk::DescriptorPoolCreateFlags
+    ) -> Self {
         let descriptor_pool = {
             let pool_info = vk::DescriptorPoolCreateInfo {
                 s_type: vk::StructureType::DESCRIPTOR_POOL_CREATE_INFO,
-                max_sets: pool_sizes.len() as u32,
+                flags,
+                max_sets,
                 pool_size_count: pool_sizes.len() as u32,
                 p_pool_sizes: pool_sizes.as_ptr(),
                 ..Default::default()
@@ -31,23 +45,31 @@ impl DescriptorPool {
         Self {
             device,
             pool: descriptor_pool,
-            sets: Vec::new(),
         }
     }
 
-    pub(crate) fn create_sets(&mut self, set_layouts: &Vec<vk::DescriptorSetLayout>) {
+    /// Fails with `ERROR_OUT_OF_POOL_MEMORY` once every set of the pool is taken.
+    pub(crate) fn allocate(&self, set_layout: vk::DescriptorSetLayout) -> VkResult<vk::DescriptorSet> {
         let allocation_info = vk::DescriptorSetAllocateInfo {
             s_type: vk::StructureType::DESCRIPTOR_SET_ALLOCATE_INFO,
             descriptor_pool: self.pool,
-            descriptor_set_count: set_layouts.len() as u32,
-            p_set_layouts: set_layouts.as_ptr(),
+            descriptor_set_count: 1,
+            p_set_layouts: &set_layout,
             ..Default::default()
         };
-        
-        self.sets = unsafe {
+
+        unsafe {
             self.device.logical.allocate_descriptor_sets(&allocation_info)
-                .expect("Failed to allocate descriptor sets")
-        };
+                .map(|sets| sets[0])
+        }
+    }
+
+    /// Give a set of a `freeable` pool back, the GPU must be done with it.
+    pub(crate) fn free(&self, set: vk::DescriptorSet) {
+        unsafe {
+            self.device.logical.free_descriptor_sets(self.pool, &[set])
+                .expect("Failed to free descriptor set")
+        }
     }
 
     pub(crate) fn update_sets(&self, writes: Vec<vk::WriteDescriptorSet>) {
@@ -56,19 +78,6 @@ impl DescriptorPool {
         };
     }
 
-    pub(crate) fn bind(&self, command_buffer: vk::CommandBuffer, layout: vk::PipelineLayout, i: usize) {
-        unsafe {
-            self.device.logical.cmd_bind_descriptor_sets(
-                command_buffer,
-                vk::PipelineBindPoint::GRAPHICS,
-                layout,
-                0,
-                &[self.sets[0], self.sets[i + 1]],
-                &[],
-            );
-        }
-    }
-
     pub(crate) fn destroy(&self) {
         unsafe {
             self.device.logical.destroy_descriptor_pool(self.pool, None)
@@ -115,12 +124,14 @@ pub enum DescriptorInfo {
 }
 
 impl DescriptorInfo {
-    pub(crate) fn buffer(buffer: vk::Buffer) -> Self {
+    /// A `range` bytes window of the buffer, for dynamic descriptors that move it with an offset,
+    /// or the whole buffer with `vk::WHOLE_SIZE`.
+    pub(crate) fn buffer_range(buffer: vk::Buffer, range: vk::DeviceSize) -> Self {
         Self::Buffer(
             vk::DescriptorBufferInfo {
                 buffer,
                 offset: 0,
-                range: vk::WHOLE_SIZE,
+                range,
             }
         )
     }
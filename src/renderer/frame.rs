@@ -0,0 +1,127 @@
+use std::{mem::{size_of, size_of_val}, rc::Rc};
+
+use ash::vk;
+use cgmath::Matrix4;
+
+use crate::core::device::GraphicDevice;
+
+use super::{buffer::Buffer, descriptorset::{descriptor_write, DescriptorInfo, DescriptorPool}, sync_object::SyncObjects};
+
+/// Instances the instance buffer of a frame holds before it first grows.
+const INITIAL_INSTANCES: usize = 1024;
+
+/// What one frame in flight writes and records, so the next frame can be prepared while
+/// the GPU still reads this one.
+pub struct FrameResources {
+    device: Rc<GraphicDevice>,
+
+    /// Camera slots of the frame, read through `camera_set` at one dynamic offset per camera.
+    pub(crate) uniform_buffer: Buffer,
+    pub(crate) camera_set: vk::DescriptorSet,
+    /// Model matrix of every instance drawn in the frame, binding 1 of `camera_set`. The vertex
+    /// shader reads the one at `gl_InstanceIndex`, each batch starts at its first instance.
+    instance_buffer: Buffer,
+    instance_capacity: usize,
+
+    pub(crate) command_buffer: vk::CommandBuffer,
+    pub(crate) sync_objects: SyncObjects,
+}
+
+impl FrameResources {
+    /// `uniform_size` bytes for every camera slot, of which one camera reads `camera_size`.
+    pub fn new(
+        device: Rc<GraphicDevice>,
+        command_buffer: vk::CommandBuffer,
+        pool: &DescriptorPool,
+        camera_layout: vk::DescriptorSetLayout,
+        uniform_size: u64,
+        camera_size: u64
+    ) -> Self {
+        let uniform_buffer = Buffer::uniform(device.clone(), uniform_size);
+
+        let camera_set = pool.allocate(camera_layout).expect("Failed to allocate descriptor set!");
+        pool.update_sets(vec![
+            descriptor_write(
+                camera_set, 
+                vk::DescriptorType::UNIFORM_BUFFER_DYNAMIC, 
+                &DescriptorInfo::buffer_range(uniform_buffer.buffer, camera_size), 
+                0, 
+                1
+            )
+        ]);
+
+        let instance_buffer = Self::write_instance_set(&device, camera_set, INITIAL_INSTANCES);
+
+        let sync_objects = SyncObjects::new(device.clone());
+
+        Self {
+            device,
+            uniform_buffer,
+            camera_set,
+            instance_buffer,
+            instance_capacity: INITIAL_INSTANCES,
+            command_buffer,
+            sync_objects,
+        }
+    }
+
+    /// Wait until the GPU is done with the last submit of this frame, its resources can be
+    /// written again afterwards.
+    pub(crate) fn wait(&self) {
+        unsafe {
+            self.device.logical
+                .wait_for_fences(&[self.sync_objects.in_flight_fence], true, u64::MAX)
+                .expect("Failed to wait for Fence!");
+        }
+    }
+
+    /// Upload the models of the instances drawn in the frame, growing the buffer when they do not fit.
+    /// The GPU must be done with the frame.
+    pub(crate) fn write_instances(&mut self, models: &[Matrix4<f32>]) {
+        if models.len() > self.instance_capacity {
+            self.instance_buffer.destroy();
+
+            self.instance_capacity = models.len().next_power_of_two();
+            self.instance_buffer = Self::write_instance_set(&self.device, self.camera_set, self.instance_capacity);
+        }
+
+        if !models.is_empty() {
+            self.instance_buffer.map(models, size_of_val(models) as u64);
+        }
+    }
+
+    /// Storage buffer for `capacity` models, written into binding 1 of `camera_set`.
+    fn write_instance_set(device: &Rc<GraphicDevice>, camera_set: vk::DescriptorSet, capacity: usize) -> Buffer {
+        let instance_buffer = Buffer::storage(device.clone(), (capacity * size_of::<Matrix4<f32>>()) as u64);
+
+        let info = DescriptorInfo::buffer_range(instance_buffer.buffer, vk::WHOLE_SIZE);
+        unsafe {
+            device.logical.update_descriptor_sets(&[
+                descriptor_write(camera_set, vk::DescriptorType::STORAGE_BUFFER, &info, 1, 1)
+            ], &[]);
+        }
+
+        instance_buffer
+    }
+
+    /// Bind the camera slot at `camera_offset` bytes into the uniform buffer as set 0.
+    pub(crate) fn bind_camera(&self, layout: vk::PipelineLayout, camera_offset: u32) {
+        unsafe {
+            self.device.logical.cmd_bind_descriptor_sets(
+                self.command_buffer,
+                vk::PipelineBindPoint::GRAPHICS,
+                layout,
+                0,
+                &[self.camera_set],
+                &[camera_offset],
+            );
+        }
+    }
+
+    /// The command buffer and descriptor set go with their pools.
+    pub(crate) fn destroy(&self) {
+        self.uniform_buffer.destroy();
+        self.instance_buffer.destroy();
+        self.sync_objects.destroy();
+    }
+}
@@ -0,0 +1,79 @@
+use std::{io, path::Path, rc::Rc};
+
+use ash::vk;
+
+use crate::{core::device::GraphicDevice, image::Image};
+
+use super::{commandpool::CommandPool, descriptorset::{descriptor_write, DescriptorInfo, DescriptorPool}};
+
+/// Set of the pipeline layout the material of a draw is bound to, after the cameras.
+pub const MATERIAL_SET: u32 = 1;
+
+/// Most materials loaded at once, each takes a descriptor set of the material pool.
+pub const MAX_MATERIALS: u32 = 256;
+
+/// Texture of a draw, with its own descriptor set so materials come and go one at a time.
+pub struct Material {
+    device: Rc<GraphicDevice>,
+
+    texture: Image,
+    set: vk::DescriptorSet,
+}
+
+impl Material {
+    pub fn new(
+        device: Rc<GraphicDevice>,
+        command_pool: &CommandPool,
+        pool: &DescriptorPool,
+        set_layout: vk::DescriptorSetLayout,
+        texture_path: &Path
+    ) -> io::Result<Self> {
+        let texture = Image::new(device.clone(), command_pool, texture_path)?;
+
+        let set = match pool.allocate(set_layout) {
+            Ok(set) => set,
+            Err(error) => {
+                texture.destroy();
+
+                return Err(io::Error::new(
+                    io::ErrorKind::OutOfMemory,
+                    format!("{}: no descriptor set left, at most {MAX_MATERIALS} materials are loaded at once: {error}", texture_path.display())
+                ));
+            }
+        };
+        pool.update_sets(vec![
+            descriptor_write(
+                set,
+                vk::DescriptorType::COMBINED_IMAGE_SAMPLER,
+                &DescriptorInfo::image(texture.sampler, texture.view),
+                0,
+                1
+            )
+        ]);
+
+        Ok(Self {
+            device,
+            texture,
+            set,
+        })
+    }
+
+    pub(crate) fn bind(&self, command_buffer: vk::CommandBuffer, layout: vk::PipelineLayout) {
+        unsafe {
+            self.device.logical.cmd_bind_descriptor_sets(
+                command_buffer,
+                vk::PipelineBindPoint::GRAPHICS,
+                layout,
+                MATERIAL_SET,
+                &[self.set],
+                &[],
+            );
+        }
+    }
+
+    /// `pool` must be the freeable pool the set came from.
+    pub(crate) fn destroy(&self, pool: &DescriptorPool) {
+        pool.free(self.set);
+        self.texture.destroy();
+    }
+}
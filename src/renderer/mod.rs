@@ -8,29 +8,40 @@ pub(crate) mod shader;
 pub(crate) mod swapchain;
 pub(crate) mod render_pass;
 pub(crate) mod buffer;
+pub(crate) mod offscreen_image;
+pub(crate) mod render_target;
+pub(crate) mod material;
+pub(crate) mod frame;
 mod sync_object;
 
 use ash::{
     extensions::{ext, khr},
     vk,
 };
-use cgmath::{Matrix, Matrix4, SquareMatrix};
+use cgmath::{Matrix4, SquareMatrix, Vector2, Vector3};
 
 use core::ffi::{c_char, c_void, CStr};
-use std::{ffi::CString, mem::{size_of, size_of_val}, path::Path, ptr, rc::Rc, slice};
+use std::{collections::HashMap, ffi::CString, io, mem::{self, size_of, size_of_val}, path::Path, ptr, rc::Rc};
 
 use crate::{
-    app::NAME, core::{camera::{Camera, ProjectionViewObject}, device::GraphicDevice, entity::{Entity, EntityJoin}, surface::{Surface, Win32Window}}, image::{check_mipmap_support, Image}, mesh::Mesh
+    app::NAME, core::{camera::{Camera, DepthConvention, ProjectionViewObject, Viewport}, device::GraphicDevice, entity::{EntityId, EntityJoin, MaterialRef, MeshRef, Transform}, scene::{MeshAsset, Scene, SceneCamera, TextureAsset}, surface::Surface, window::Window}, image::check_mipmap_support, mesh::Mesh
 };
 
 use self::{
-    buffer::Buffer, color_image::ColorImage, commandpool::CommandPool, debug_object::DebugObjects, depth_image::DepthImage, descriptorset::{descriptor_write, DescriptorInfo, DescriptorLayout, DescriptorPool}, pipeline::GraphicPipeline, render_pass::RenderPass, swapchain::SwapChain, sync_object::{SyncObjects, MAX_FRAMES_IN_FLIGHT}
+    color_image::ColorImage, commandpool::CommandPool, debug_object::DebugObjects, depth_image::DepthImage, descriptorset::{DescriptorLayout, DescriptorPool}, frame::FrameResources, material::{Material, MATERIAL_SET, MAX_MATERIALS}, offscreen_image::OffscreenImage, pipeline::GraphicPipeline, render_pass::RenderPass, render_target::RenderTarget, swapchain::SwapChain, sync_object::MAX_FRAMES_IN_FLIGHT
 };
 
-pub fn required_extension_names() -> Vec<*const i8> {
+pub fn required_extension_names(window: &dyn Window) -> Vec<*const i8> {
     vec![
         khr::Surface::name().as_ptr(),
-        khr::Win32Surface::name().as_ptr(),
+        window.handle().surface_extension_name().as_ptr(),
+        ext::DebugUtils::name().as_ptr(),
+    ]
+}
+
+/// Instance extensions for rendering without a window, nothing surface related.
+pub fn headless_extension_names() -> Vec<*const i8> {
+    vec![
         ext::DebugUtils::name().as_ptr(),
     ]
 }
@@ -82,8 +93,89 @@ pub fn vk_to_string(raw_string_array: &[c_char]) -> String {
         .to_owned()
 }
 
-pub fn size_of_array<T>(data: &[T]) -> usize {
-    size_of::<T>() * data.len()
+/// Most cameras drawn in one frame, extra cameras are ignored.
+pub const MAX_CAMERAS: usize = 4;
+
+/// View and projection of one camera in the uniform buffer. Vulkan caps the dynamic offset
+/// alignment at 256 bytes, so slots this far apart can be bound on every device.
+#[repr(C, align(256))]
+#[derive(Clone, Copy)]
+struct CameraSlot(ProjectionViewObject);
+
+/// Closest entity under a screen point.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Pick {
+    pub entity: EntityId,
+    /// World space position of the hit.
+    pub point: Vector3<f32>,
+    /// Distance from the near plane along the picking ray.
+    pub distance: f32,
+    /// Weights of the corners of the hit triangle, `None` when the mesh kept no geometry
+    /// and only its bounds were hit.
+    pub barycentrics: Option<Vector3<f32>>,
+}
+
+/// One mesh drawn with one texture at `model`.
+#[derive(Clone, Copy, PartialEq)]
+struct Draw {
+    model: Matrix4<f32>,
+    mesh: usize,
+    material: usize,
+}
+
+/// Mesh or material at an index of the renderer, entities pointing at an unloaded one are skipped.
+enum Slot<T> {
+    Loaded(T),
+    /// Its file failed to load, it stays in the saved scene.
+    Failed,
+    /// Removed while running, it is left out of the saved scene.
+    Removed,
+}
+
+impl<T> Slot<T> {
+    fn loaded(&self) -> Option<&T> {
+        match self {
+            Slot::Loaded(asset) => Some(asset),
+            _ => None,
+        }
+    }
+}
+
+/// Instances of one mesh drawn with one texture in a single draw call, their models are `count`
+/// matrices of the instance buffer from `first`.
+#[derive(Clone, Copy, PartialEq, Debug)]
+struct Batch {
+    mesh: usize,
+    material: usize,
+    first: u32,
+    count: u32,
+}
+
+/// Group the draws of every camera by mesh and material, in the order each pair first shows up.
+/// Returns the batches of each camera and the models they point into, camera after camera.
+fn batch(draws: &[Vec<Draw>]) -> (Vec<Vec<Batch>>, Vec<Matrix4<f32>>) {
+    let mut instances = Vec::new();
+
+    let batches = draws.iter().map(|draws| {
+        let mut groups: Vec<(usize, usize, Vec<Matrix4<f32>>)> = Vec::new();
+        let mut group_of = HashMap::new();
+
+        for draw in draws {
+            let group = *group_of.entry((draw.mesh, draw.material)).or_insert_with(|| {
+                groups.push((draw.mesh, draw.material, Vec::new()));
+                groups.len() - 1
+            });
+            groups[group].2.push(draw.model);
+        }
+
+        groups.into_iter().map(|(mesh, material, models)| {
+            let batch = Batch { mesh, material, first: instances.len() as u32, count: models.len() as u32 };
+            instances.extend(models);
+            batch
+        }).collect()
+    }).collect();
+
+    (batches, instances)
 }
 
 pub struct Renderer {
@@ -92,11 +184,11 @@ pub struct Renderer {
     pub(crate) device: Rc<GraphicDevice>,
     instance: ash::Instance,
 
-    surface: Surface,
+    surface: Option<Surface>,
 
     debug_objects: DebugObjects,
 
-    swapchain: SwapChain,
+    target: RenderTarget,
 
     depth_image: DepthImage,
     color_image: ColorImage,
@@ -107,57 +199,96 @@ pub struct Renderer {
 
     pipeline: GraphicPipeline,
 
-    texture: Image,
-    mesh: Mesh,
-
-    texture2: Image,
-    mesh2: Mesh,
-
-    projection_view: ProjectionViewObject,
-    uniform_buffer: Buffer,
+    /// Meshes and materials entities point at with `MeshRef` and `MaterialRef`. Indices are never
+    /// reused by other assets.
+    meshes: Vec<Slot<Mesh>>,
+    materials: Vec<Slot<Material>>,
+    /// Files of every mesh and material slot and the prefabs of the scene, without entities
+    /// or cameras.
+    assets: Scene,
 
     command_pool: CommandPool,
 
     set_layouts: Vec<DescriptorLayout>,
     descriptor_pool: DescriptorPool,
+    material_pool: DescriptorPool,
 
-    sync_objects: SyncObjects,
+    frames: Vec<FrameResources>,
     current_frame: usize,
 
     is_framebuffer_resized: bool,
+    /// Depth layout the pipeline and recorded passes use, follows the camera projection.
+    depth_convention: DepthConvention,
+    /// Viewport of each camera of the frame being recorded.
+    viewports: Vec<Viewport>,
+    /// Batches of each camera of the frame being recorded, after culling.
+    batches: Vec<Vec<Batch>>,
+    /// Models of the instances the batches point into.
+    instances: Vec<Matrix4<f32>>,
+    culled_count: usize,
 }
 
 impl Renderer {
-    pub fn new(window: &Win32Window) -> Self {
+    pub fn new(window: &dyn Window, scene: &Scene) -> Self {
         let entry = ash::Entry::linked();
-        let instance = Self::create_instance(&entry);
+        let instance = Self::create_instance(&entry, &required_extension_names(window));
         
-        let surface = Surface::new(&entry, &instance, &window);
+        let surface = Surface::new(&entry, &instance, window);
 
         let device = Rc::new(GraphicDevice::new(&instance, &surface));
-        
+
+        let swapchain = SwapChain::new(
+            &instance, device.clone(), window.size(), &surface
+        );
+
+        Self::with_target(
+            &entry, instance, Some(surface), device, RenderTarget::Swapchain(swapchain), scene
+        )
+    }
+
+    /// Renderer without window, surface or swapchain. Frames are resolved into
+    /// an offscreen image that can be read back with `read_frame`.
+    pub fn headless(size: Vector2<u32>, scene: &Scene) -> Self {
+        let entry = ash::Entry::linked();
+        let instance = Self::create_instance(&entry, &headless_extension_names());
+
+        let device = Rc::new(GraphicDevice::headless(&instance));
+
+        let offscreen = OffscreenImage::new(
+            device.clone(), vk::Extent2D { width: size.x, height: size.y }
+        );
+
+        Self::with_target(
+            &entry, instance, None, device, RenderTarget::Offscreen(offscreen), scene
+        )
+    }
+
+    fn with_target(
+        entry: &ash::Entry, 
+        instance: ash::Instance, 
+        surface: Option<Surface>, 
+        device: Rc<GraphicDevice>, 
+        mut target: RenderTarget,
+        scene: &Scene
+    ) -> Self {
         check_mipmap_support(&instance, device.physical);
 
         let msaa_samples = Self::get_max_usable_sample_count(&instance, device.physical);
         
-        let debug_objects = DebugObjects::new(&entry, &instance);
-
-        let mut swapchain = SwapChain::new(
-            &instance, device.clone(), window.size, &surface
-        );
+        let debug_objects = DebugObjects::new(entry, &instance);
         
         let color_image = ColorImage::new(
-            device.clone(), &swapchain.format, &swapchain.extent, msaa_samples
+            device.clone(), &target.format(), &target.extent(), msaa_samples
         );
         let depth_image = DepthImage::new(
-            &instance, device.clone(), &swapchain.extent, msaa_samples
+            &instance, device.clone(), &target.extent(), msaa_samples
         );
 
         let render_pass = RenderPass::new(
-            &instance, device.clone(), &swapchain.format, msaa_samples
+            &instance, device.clone(), &target.format(), msaa_samples, target.final_layout()
         );
 
-        swapchain.create_framebuffer(
+        target.create_framebuffer(
             &render_pass.pass, 
             depth_image.image_view, 
             color_image.image_view
@@ -165,22 +296,21 @@ impl Renderer {
         
         let mut command_pool = CommandPool::new(device.clone());
 
+        // set 0 holds the cameras and instances of the frame, set 1 the material of each batch.
         let set_layouts = vec![
             DescriptorLayout::new(device.clone(), vec![
                 vk::DescriptorSetLayoutBinding { 
                     binding: 0, 
-                    descriptor_type: vk::DescriptorType::UNIFORM_BUFFER, 
+                    descriptor_type: vk::DescriptorType::UNIFORM_BUFFER_DYNAMIC, 
                     descriptor_count: 1, 
                     stage_flags: vk::ShaderStageFlags::VERTEX, 
                     ..Default::default()
-                }
-            ]),
-            DescriptorLayout::new(device.clone(), vec![
+                },
                 vk::DescriptorSetLayoutBinding { 
-                    binding: 0, 
-                    descriptor_type: vk::DescriptorType::COMBINED_IMAGE_SAMPLER, 
+                    binding: 1, 
+                    descriptor_type: vk::DescriptorType::STORAGE_BUFFER, 
                     descriptor_count: 1, 
-                    stage_flags: vk::ShaderStageFlags::FRAGMENT, 
+                    stage_flags: vk::ShaderStageFlags::VERTEX, 
                     ..Default::default()
                 }
             ]),
@@ -192,119 +322,59 @@ impl Renderer {
                     stage_flags: vk::ShaderStageFlags::FRAGMENT, 
                     ..Default::default()
                 }
-            ])
+            ]),
         ];
-        
-        let texture = Image::new(
-            device.clone(), 
-            &command_pool, 
-            Path::new("res/Rail.png")
-        );
-        let mesh = Mesh::from_obj(
-            device.clone(), 
-            &command_pool, 
-            Path::new("res/Rail.obj")
-        );
-
-        let texture2 = Image::new(
-            device.clone(), 
-            &command_pool, 
-            Path::new("res/Viking.png")
-        );
-        let mesh2 = Mesh::from_obj(
-            device.clone(), 
-            &command_pool, 
-            Path::new("res/Viking.obj")
-        );
-
-        let object = Entity::new();
-        let mut object2 = Entity::new();
-        object2.position.x = -2.0;
 
         let mut entities = EntityJoin::new();
-        entities.add(object);
-        entities.add(object2);
+        scene.spawn(&mut entities);
+        entities.update_transforms();
 
         let pipeline = GraphicPipeline::new(
             device.clone(), 
             &render_pass.pass, 
-            &swapchain, 
             {
                 &set_layouts.iter().map(|x| -> vk::DescriptorSetLayout {
                         x.layout
                     }
                 ).collect()
             }, 
-            size_of_array(&entities.get_transforms()) as u32,
-            msaa_samples
+            msaa_samples,
+            DepthConvention::Standard
         );
 
-        let projection_view = ProjectionViewObject {
-            view: Matrix4::identity(),
-            proj: Matrix4::identity()
-        };
-        let uniform_buffer = Buffer::uniform(device.clone(), size_of_val(&projection_view) as u64);
-
-        let mut descriptor_pool = DescriptorPool::new(device.clone(), 
-            vec![
-                vk::DescriptorPoolSize { 
-                    ty: vk::DescriptorType::UNIFORM_BUFFER, 
-                    descriptor_count: 1 
-                },
-                vk::DescriptorPoolSize { 
-                    ty: vk::DescriptorType::COMBINED_IMAGE_SAMPLER, 
-                    descriptor_count: 1 
-                },
-                vk::DescriptorPoolSize { 
-                    ty: vk::DescriptorType::COMBINED_IMAGE_SAMPLER, 
-                    descriptor_count: 1 
-                }
-            ]
-        );
-        descriptor_pool.create_sets({
-                &set_layouts.iter().map(|x| -> vk::DescriptorSetLayout {
-                        x.layout
-                    }
-                ).collect()
+        // one camera set per frame in flight.
+        let descriptor_pool = DescriptorPool::new(device.clone(), vec![
+            vk::DescriptorPoolSize { 
+                ty: vk::DescriptorType::UNIFORM_BUFFER_DYNAMIC, 
+                descriptor_count: MAX_FRAMES_IN_FLIGHT as u32 
+            },
+            vk::DescriptorPoolSize { 
+                ty: vk::DescriptorType::STORAGE_BUFFER, 
+                descriptor_count: MAX_FRAMES_IN_FLIGHT as u32 
             }
-        );
+        ], MAX_FRAMES_IN_FLIGHT as u32);
 
-        let descriptor_infos = vec![
-            DescriptorInfo::buffer(uniform_buffer.buffer),
-            DescriptorInfo::image(texture.sampler, texture.view),
-            DescriptorInfo::image(texture2.sampler, texture2.view)
-        ];
-        let descriptor_writes = vec![
-            descriptor_write(
-                descriptor_pool.sets[0], 
-                vk::DescriptorType::UNIFORM_BUFFER, 
-                &descriptor_infos[0], 
-                0, 
-                1
-            ),
-            descriptor_write(
-                descriptor_pool.sets[1], 
-                vk::DescriptorType::COMBINED_IMAGE_SAMPLER, 
-                &descriptor_infos[1], 
-                0, 
-                1
-            ),
-            descriptor_write(
-                descriptor_pool.sets[2], 
-                vk::DescriptorType::COMBINED_IMAGE_SAMPLER, 
-                &descriptor_infos[2], 
-                0, 
-                1
-            )
-        ];
-
-        descriptor_pool.update_sets(descriptor_writes);
+        let material_pool = DescriptorPool::freeable(device.clone(), vec![
+            vk::DescriptorPoolSize { 
+                ty: vk::DescriptorType::COMBINED_IMAGE_SAMPLER, 
+                descriptor_count: MAX_MATERIALS 
+            }
+        ], MAX_MATERIALS);
             
-        let sync_objects = SyncObjects::new(device.clone());
-
-        command_pool.allocate_buffers(&swapchain.framebuffers);
+        command_pool.allocate_buffers(MAX_FRAMES_IN_FLIGHT);
+
+        let frames = command_pool.buffers.iter().map(|&command_buffer| {
+            FrameResources::new(
+                device.clone(),
+                command_buffer,
+                &descriptor_pool,
+                set_layouts[0].layout,
+                (size_of::<CameraSlot>() * MAX_CAMERAS) as u64,
+                size_of::<ProjectionViewObject>() as u64
+            )
+        }).collect();
 
-        Self {
+        let mut renderer = Self {
             msaa_samples,
 
             device,
@@ -314,7 +384,7 @@ impl Renderer {
 
             debug_objects,
 
-            swapchain,
+            target,
 
             depth_image,
             color_image,
@@ -325,25 +395,30 @@ impl Renderer {
 
             pipeline,
 
-            texture,
-            mesh,
-
-            texture2,
-            mesh2,
-
-            projection_view,
-            uniform_buffer,
+            meshes: Vec::new(),
+            materials: Vec::new(),
+            assets: Scene { cameras: Vec::new(), entities: Vec::new(), ..scene.clone() },
 
             command_pool,
 
             set_layouts,
             descriptor_pool,
+            material_pool,
 
-            sync_objects,
+            frames,
             current_frame: 0,
 
             is_framebuffer_resized: false,
-        }
+            depth_convention: DepthConvention::Standard,
+            viewports: vec![Viewport::FULL],
+            batches: vec![Vec::new()],
+            instances: Vec::new(),
+            culled_count: 0,
+        };
+
+        renderer.load_assets();
+
+        renderer
     }
 
     fn get_max_usable_sample_count(
@@ -384,7 +459,7 @@ impl Renderer {
         vk::SampleCountFlags::TYPE_1
     }
 
-    fn create_instance(entry: &ash::Entry) -> ash::Instance {
+    fn create_instance(entry: &ash::Entry, extension_names: &[*const i8]) -> ash::Instance {
         if VALIDATION.is_enable && Self::check_validation_layer_support(entry) == false {
             panic!("Validation layers requested, but not available!");
         }
@@ -401,8 +476,6 @@ impl Renderer {
 
         let debug_utils_create_info = populate_debug_messenger_create_info();
 
-        let extension_names = required_extension_names();
-
         let requred_validation_layer_raw_names: Vec<CString> = VALIDATION
             .required_validation_layers
             .iter()
@@ -479,82 +552,72 @@ impl Renderer {
         true
     }
 
-    pub(crate) fn record(&mut self) {
-        for (i, &command_buffer) in self.command_pool.buffers.iter().enumerate() {
-            self.command_pool.begin_command_buffer(command_buffer);
+    /// Record the draws of the current frame into its command buffer, rendering to `framebuffer`.
+    fn record(&self, framebuffer: vk::Framebuffer) {
+        let frame = &self.frames[self.current_frame];
+        let command_buffer = frame.command_buffer;
 
-            self.render_pass.begin(
-                command_buffer, 
-                self.swapchain.extent, 
-                self.swapchain.framebuffers[i]
-            );
+        self.command_pool.begin_command_buffer(command_buffer);
 
-            self.pipeline.bind(command_buffer);
+        self.render_pass.begin(
+            command_buffer, 
+            self.target.extent(), 
+            framebuffer,
+            self.depth_convention
+        );
 
-            {
-                self.mesh.bind(command_buffer);
-
-                self.descriptor_pool.bind(command_buffer, self.pipeline.layout, 0);
-
-                unsafe { 
-                    let model_bytes = slice::from_raw_parts(
-                        self.entities.get_transforms()[0].as_ptr() as *const u8,
-                        size_of::<Matrix4<f32>>()
-                    );
-                
-                    self.device.logical.cmd_push_constants(
-                        command_buffer, 
-                        self.pipeline.layout, 
-                        vk::ShaderStageFlags::VERTEX, 
-                        0, 
-                        model_bytes
-                    ) 
-                };
-                self.mesh.draw(command_buffer, 1);
+        self.pipeline.bind(command_buffer);
+
+        let extent = self.target.extent();
+        let extent = Vector2 { x: extent.width, y: extent.height };
+
+        for (camera_index, viewport) in self.viewports.iter().enumerate() {
+            let (offset, size) = viewport.pixels(extent);
+            let area = vk::Rect2D {
+                offset: vk::Offset2D { x: offset.x, y: offset.y },
+                extent: vk::Extent2D { width: size.x, height: size.y },
+            };
+
+            // an earlier camera may have drawn here already, e.g. behind a picture in picture.
+            if camera_index > 0 {
+                self.render_pass.clear(command_buffer, area, self.depth_convention);
             }
+            self.pipeline.set_viewport(command_buffer, area);
 
-            {
-                self.mesh2.bind(command_buffer);
-
-                self.descriptor_pool.bind(command_buffer, self.pipeline.layout, 1);
-
-                unsafe { 
-                    let model_bytes = slice::from_raw_parts(
-                        self.entities.get_transforms()[1].as_ptr() as *const u8,
-                        size_of::<Matrix4<f32>>()
-                    );
-                
-                    self.device.logical.cmd_push_constants(
-                        command_buffer, 
-                        self.pipeline.layout, 
-                        vk::ShaderStageFlags::VERTEX, 
-                        0, 
-                        model_bytes
-                    ) 
+            let camera_offset = (camera_index * size_of::<CameraSlot>()) as u32;
+            frame.bind_camera(self.pipeline.layout, camera_offset);
+
+            for batch in &self.batches[camera_index] {
+                let (Some(mesh), Some(material)) = (self.meshes[batch.mesh].loaded(), self.materials[batch.material].loaded()) else {
+                    continue;
                 };
-                self.mesh2.draw(command_buffer, 1);
-            }
-            
-            self.render_pass.end(command_buffer);
+                mesh.bind(command_buffer);
+                material.bind(command_buffer, self.pipeline.layout);
 
-            self.command_pool.end_command_buffer(command_buffer);
+                mesh.draw(command_buffer, batch.count, batch.first);
+            }
         }
+
+        self.render_pass.end(command_buffer);
+
+        self.command_pool.end_command_buffer(command_buffer);
     }
 
-    pub(crate) fn draw(&mut self, window: &Win32Window, camera: &Camera) {
-        let wait_fences = [self.sync_objects.in_flight_fences[self.current_frame]];
+    /// Draw the scene once per camera, each into its own viewport.
+    pub(crate) fn draw(&mut self, window: &dyn Window, cameras: &[&Camera]) {
+        self.follow_cameras(cameras);
 
-        unsafe {
-            self.device.logical
-                .wait_for_fences(&wait_fences, true, std::u64::MAX)
-                .expect("Failed to wait for Fence!");
-        }
+        // the frame's buffers are written below, the GPU must be done reading them.
+        self.frames[self.current_frame].wait();
+        let sync_objects = &self.frames[self.current_frame].sync_objects;
+        let wait_fences = [sync_objects.in_flight_fence];
 
         let (image_index, _is_sub_optimal) = unsafe {
-            let result = self.swapchain.loader.acquire_next_image(
-                self.swapchain.swapchain,
+            let swapchain = self.target.swapchain();
+            let result = swapchain.loader.acquire_next_image(
+                swapchain.swapchain,
                 std::u64::MAX,
-                self.sync_objects.image_available_semaphores[self.current_frame],
+                sync_objects.image_available_semaphore,
                 vk::Fence::null(),
             );
             match result {
@@ -569,11 +632,16 @@ impl Renderer {
             }
         };
 
-        self.update_uniform_buffer(camera);
+        self.update_uniform_buffer(cameras);
+        self.frames[self.current_frame].write_instances(&self.instances);
+        self.record(self.target.framebuffers()[image_index as usize]);
+
+        let frame = &self.frames[self.current_frame];
+        let command_buffers = [frame.command_buffer];
 
-        let wait_semaphores = [self.sync_objects.image_available_semaphores[self.current_frame]];
+        let wait_semaphores = [frame.sync_objects.image_available_semaphore];
         let wait_stages = [vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT];
-        let signal_semaphores = [self.sync_objects.render_finished_semaphores[self.current_frame]];
+        let signal_semaphores = [frame.sync_objects.render_finished_semaphore];
 
         let submit_infos = [vk::SubmitInfo {
             s_type: vk::StructureType::SUBMIT_INFO,
@@ -582,7 +650,7 @@ impl Renderer {
             p_wait_semaphores: wait_semaphores.as_ptr(),
             p_wait_dst_stage_mask: wait_stages.as_ptr(),
             command_buffer_count: 1,
-            p_command_buffers: self.command_pool.get_buffer(image_index as usize),
+            p_command_buffers: command_buffers.as_ptr(),
             signal_semaphore_count: signal_semaphores.len() as u32,
             p_signal_semaphores: signal_semaphores.as_ptr(),
         }];
@@ -598,12 +666,13 @@ impl Renderer {
                 .queue_submit(
                     self.device.graphics_queue,
                     &submit_infos,
-                    self.sync_objects.in_flight_fences[self.current_frame],
+                    frame.sync_objects.in_flight_fence,
                 )
                 .expect("Failed to execute queue submit.");
         }
 
-        let swapchains = [self.swapchain.swapchain];
+        let swapchain = self.target.swapchain();
+        let swapchains = [swapchain.swapchain];
 
         let present_info = vk::PresentInfoKHR {
             s_type: vk::StructureType::PRESENT_INFO_KHR,
@@ -617,7 +686,7 @@ impl Renderer {
         };
 
         let result = unsafe {
-            self.swapchain
+            swapchain
                 .loader
                 .queue_present(self.device.present_queue, &present_info)
         };
@@ -636,86 +705,478 @@ impl Renderer {
 
         self.current_frame = (self.current_frame + 1) % MAX_FRAMES_IN_FLIGHT;
     }
+
+    /// Render one frame into the offscreen target and wait until it is finished.
+    pub(crate) fn draw_offscreen(&mut self, cameras: &[&Camera]) {
+        self.follow_cameras(cameras);
+
+        self.frames[self.current_frame].wait();
+
+        self.update_uniform_buffer(cameras);
+        self.frames[self.current_frame].write_instances(&self.instances);
+        self.record(self.target.framebuffers()[0]);
+
+        let frame = &self.frames[self.current_frame];
+        let command_buffers = [frame.command_buffer];
+        let wait_fences = [frame.sync_objects.in_flight_fence];
+
+        let submit_infos = [vk::SubmitInfo {
+            s_type: vk::StructureType::SUBMIT_INFO,
+            p_next: ptr::null(),
+            wait_semaphore_count: 0,
+            p_wait_semaphores: ptr::null(),
+            p_wait_dst_stage_mask: ptr::null(),
+            command_buffer_count: 1,
+            p_command_buffers: command_buffers.as_ptr(),
+            signal_semaphore_count: 0,
+            p_signal_semaphores: ptr::null(),
+        }];
+
+        unsafe {
+            self.device
+                .logical
+                .reset_fences(&wait_fences)
+                .expect("Failed to reset Fence!");
+
+            self.device
+                .logical
+                .queue_submit(
+                    self.device.graphics_queue,
+                    &submit_infos,
+                    frame.sync_objects.in_flight_fence,
+                )
+                .expect("Failed to execute queue submit.");
+
+            self.device.logical
+                .wait_for_fences(&wait_fences, true, u64::MAX)
+                .expect("Failed to wait for Fence!");
+        }
+    }
+
+    /// Read the last offscreen frame back as RGBA8 pixels, row by row from the top.
+    pub fn read_frame(&self) -> Vec<u8> {
+        match &self.target {
+            RenderTarget::Offscreen(offscreen) => offscreen.read(&self.command_pool),
+            RenderTarget::Swapchain(_) => panic!("Only offscreen frames can be read back!"),
+        }
+    }
+
+    /// Save the last offscreen frame as a PNG file.
+    pub fn save_frame(&self, path: &Path) -> image::ImageResult<()> {
+        let extent = self.target.extent();
+
+        image::save_buffer_with_format(
+            path,
+            &self.read_frame(),
+            extent.width,
+            extent.height,
+            image::ColorType::Rgba8,
+            image::ImageFormat::Png,
+        )
+    }
     
     pub(crate) fn cleanup_swapchain(&self) {
         self.depth_image.destroy();
         self.color_image.destroy();
 
-        self.command_pool.free_buffers();
-
-        self.swapchain.destroy_framebuffers();
+        self.target.destroy_framebuffers();
 
         self.pipeline.destroy();
 
         self.render_pass.destroy();
 
-        self.swapchain.destroy();
+        self.target.destroy();
     }
 
-    fn recreate_swapchain(&mut self, window: &Win32Window) {
+    fn recreate_swapchain(&mut self, window: &dyn Window) {
         self.device.wait_idle();
 
         self.cleanup_swapchain();
 
-        self.swapchain = SwapChain::new(
+        self.target = RenderTarget::Swapchain(SwapChain::new(
             &self.instance, 
             self.device.clone(), 
-            window.size, 
-            &self.surface
-        );
+            window.size(), 
+            self.surface.as_ref().expect("Offscreen render target can not be recreated!")
+        ));
         self.render_pass = RenderPass::new(
             &self.instance,
             self.device.clone(),
-            &self.swapchain.format,
+            &self.target.format(),
             self.msaa_samples,
+            self.target.final_layout(),
         );
         self.pipeline = GraphicPipeline::new(
             self.device.clone(),
             &self.render_pass.pass,
-            &self.swapchain,
             {
                 &self.set_layouts.iter().map(|x| -> vk::DescriptorSetLayout {
                         x.layout
                     }
                 ).collect()
             },
-            size_of_array(&self.entities.get_transforms()) as u32,
             self.msaa_samples,
+            self.depth_convention,
         );
         self.color_image = ColorImage::new(
             self.device.clone(), 
-            &self.swapchain.format,
-            &self.swapchain.extent, 
+            &self.target.format(),
+            &self.target.extent(), 
             self.msaa_samples
         );
         self.depth_image = DepthImage::new(
             &self.instance,
             self.device.clone(),
-            &self.swapchain.extent,
+            &self.target.extent(),
             self.msaa_samples,
         );
 
-        self.swapchain.create_framebuffer(
+        self.target.create_framebuffer(
             &self.render_pass.pass, 
             self.depth_image.image_view, 
             self.color_image.image_view
         );
+    }
+    
+    /// Gather the viewports and culled batches of every camera for the frame to record, and rebuild
+    /// the pipeline when the first camera switches between standard and reversed depth. Every
+    /// camera of a frame shares the depth convention of the first one.
+    fn follow_cameras(&mut self, cameras: &[&Camera]) {
+        assert!(!cameras.is_empty(), "At least one camera is needed to draw!");
+
+        let cameras = &cameras[..cameras.len().min(MAX_CAMERAS)];
+
+        self.entities.update_transforms();
+
+        let drawables = self.drawables();
+        let draws: Vec<Vec<Draw>> = cameras.iter().map(|camera| self.cull(camera, &drawables)).collect();
+        self.culled_count = drawables.len() * cameras.len() - draws.iter().map(Vec::len).sum::<usize>();
+
+        self.viewports = cameras.iter().map(|camera| camera.viewport).collect();
+        (self.batches, self.instances) = batch(&draws);
+
+        let depth_convention = cameras[0].projection.depth_convention();
+        if depth_convention != self.depth_convention {
+            // frames still in flight use the old pipeline.
+            self.device.wait_idle();
 
-        self.command_pool.allocate_buffers(&self.swapchain.framebuffers);
+            self.depth_convention = depth_convention;
+            self.rebuild_pipeline();
+        }
+    }
 
-        self.record();
+    /// Every entity with a `Transform`, a `MeshRef` and a `MaterialRef` the renderer has loaded.
+    fn drawables(&self) -> Vec<(EntityId, Draw)> {
+        self.entities.query::<(&Transform, &MeshRef, &MaterialRef)>()
+            .filter(|(_, (_, mesh, material))| {
+                self.meshes.get(mesh.0).and_then(Slot::loaded).is_some()
+                    && self.materials.get(material.0).and_then(Slot::loaded).is_some()
+            })
+            .filter_map(|(id, (_, mesh, material))| {
+                let model = self.entities.world_transform(id)?;
+                Some((id, Draw { model, mesh: mesh.0, material: material.0 }))
+            })
+            .collect()
     }
-    
-    fn update_uniform_buffer(&mut self, camera: &Camera) {
-        self.projection_view.view = camera.get_view();
-        self.projection_view.proj = camera.get_projection();
 
-        self.uniform_buffer.map(
-            &[self.projection_view], 
-            size_of_val(&self.projection_view) as u64
+    /// Draws whose world bounds touch the frustum of `camera`.
+    fn cull(&self, camera: &Camera, drawables: &[(EntityId, Draw)]) -> Vec<Draw> {
+        let extent = self.target.extent();
+        let frustum = camera.frustum(Vector2 { x: extent.width, y: extent.height });
+
+        drawables.iter()
+            .map(|&(_, draw)| draw)
+            .filter(|draw| {
+                let Some(mesh) = self.meshes[draw.mesh].loaded() else {
+                    return false;
+                };
+
+                frustum.intersects_sphere(&mesh.bounding_sphere.transform(&draw.model))
+                    && frustum.intersects_aabb(&mesh.bounds.transform(&draw.model))
+            })
+            .collect()
+    }
+
+    /// Size of the render target in pixels, the swapchain may lag behind a resized window.
+    pub fn extent(&self) -> Vector2<u32> {
+        let extent = self.target.extent();
+
+        Vector2 { x: extent.width, y: extent.height }
+    }
+
+    /// Closest entity `camera` sees under the pixel `point` of the render target.
+    pub fn pick(&self, camera: &Camera, point: Vector2<f32>) -> Option<Pick> {
+        let ray = camera.screen_point_to_ray(point, self.extent());
+
+        self.drawables().into_iter()
+            .filter_map(|(entity, draw)| {
+                let mesh = self.meshes[draw.mesh].loaded()?;
+                ray.intersect_aabb(&mesh.bounds.transform(&draw.model))?;
+
+                // the ray keeps its parameter in model space, so hits compare across entities.
+                let model_ray = ray.transform(&draw.model.invert()?);
+                let (distance, barycentrics) = match mesh.raycast(&model_ray) {
+                    Some(hit) => (hit.t, Some(hit.barycentrics)),
+                    None if mesh.has_geometry() => return None,
+                    None => (model_ray.intersect_aabb(&mesh.bounds)?, None),
+                };
+
+                Some(Pick { entity, point: ray.at(distance), distance, barycentrics })
+            })
+            .min_by(|a, b| a.distance.total_cmp(&b.distance))
+    }
+
+    /// Entity draws skipped in the last frame, summed over every camera.
+    pub fn culled_count(&self) -> usize {
+        self.culled_count
+    }
+
+    fn rebuild_pipeline(&mut self) {
+        self.pipeline.destroy();
+
+        self.pipeline = GraphicPipeline::new(
+            self.device.clone(),
+            &self.render_pass.pass,
+            {
+                &self.set_layouts.iter().map(|x| -> vk::DescriptorSetLayout {
+                        x.layout
+                    }
+                ).collect()
+            },
+            self.msaa_samples,
+            self.depth_convention,
         );
     }
+
+    fn update_uniform_buffer(&mut self, cameras: &[&Camera]) {
+        let extent = self.target.extent();
+        let extent = Vector2 { x: extent.width, y: extent.height };
+
+        let slots: Vec<CameraSlot> = cameras.iter().take(MAX_CAMERAS).map(|camera| {
+            CameraSlot(ProjectionViewObject {
+                view: camera.get_view(),
+                proj: camera.get_projection(extent),
+            })
+        }).collect();
+
+        // only the copy of the current frame, the GPU may still read the others.
+        self.frames[self.current_frame].uniform_buffer.map(&slots, size_of_val(slots.as_slice()) as u64);
+    }
     
+    pub fn entities(&self) -> &EntityJoin {
+        &self.entities
+    }
+
+    /// Entities can be added, moved and removed between frames, the next draw picks the changes up.
+    pub fn entities_mut(&mut self) -> &mut EntityJoin {
+        &mut self.entities
+    }
+
+    /// Scene of the loaded assets and the entities as they are now, seen by `cameras`.
+    /// Entities keep no reference to removed assets.
+    pub fn scene(&self, cameras: Vec<SceneCamera>) -> Scene {
+        let mut scene = Scene::from_entities(&self.entities, Scene { cameras, ..self.assets.clone() });
+
+        let removed_meshes: Vec<String> = self.assets.meshes.iter().zip(&self.meshes)
+            .filter(|(_, mesh)| matches!(mesh, Slot::Removed))
+            .map(|(asset, _)| asset.name.clone())
+            .collect();
+        let removed_textures: Vec<String> = self.assets.textures.iter().zip(&self.materials)
+            .filter(|(_, material)| matches!(material, Slot::Removed))
+            .map(|(asset, _)| asset.name.clone())
+            .collect();
+
+        scene.meshes.retain(|mesh| !removed_meshes.contains(&mesh.name));
+        scene.textures.retain(|texture| !removed_textures.contains(&texture.name));
+        for entity in &mut scene.entities {
+            entity.mesh = entity.mesh.take().filter(|mesh| !removed_meshes.contains(mesh));
+            entity.texture = entity.texture.take().filter(|texture| !removed_textures.contains(texture));
+        }
+
+        scene
+    }
+
+    /// Load the prefabs whose file changed and update their instances, returns their names and
+    /// the errors of those that failed to load.
+    pub fn reload_prefabs(&mut self) -> (Vec<String>, Vec<io::Error>) {
+        let reloaded = self.assets.reload_prefabs(&mut self.entities);
+
+        // the prefabs may use assets the scene did not.
+        self.load_assets();
+
+        reloaded
+    }
+
+    /// Load the assets listed past the last mesh and material slot. Files that fail to load
+    /// leave their slot unloaded.
+    fn load_assets(&mut self) {
+        while self.meshes.len() < self.assets.meshes.len() {
+            let asset = &self.assets.meshes[self.meshes.len()];
+            let mesh = self.load_mesh(asset).unwrap_or_else(|error| {
+                println!("Failed to load mesh {}: {error}", asset.name);
+                Slot::Failed
+            });
+            self.meshes.push(mesh);
+        }
+
+        while self.materials.len() < self.assets.textures.len() {
+            let asset = &self.assets.textures[self.materials.len()];
+            let material = self.load_material(asset).unwrap_or_else(|error| {
+                println!("Failed to load texture {}: {error}", asset.name);
+                Slot::Failed
+            });
+            self.materials.push(material);
+        }
+    }
+
+    fn load_mesh(&self, asset: &MeshAsset) -> io::Result<Slot<Mesh>> {
+        let mesh = if asset.keep_geometry {
+            Mesh::from_obj_with_geometry(self.device.clone(), &self.command_pool, &asset.path)?
+        } else {
+            Mesh::from_obj(self.device.clone(), &self.command_pool, &asset.path)?
+        };
+
+        Ok(Slot::Loaded(mesh))
+    }
+
+    fn load_material(&self, asset: &TextureAsset) -> io::Result<Slot<Material>> {
+        let material = Material::new(
+            self.device.clone(),
+            &self.command_pool,
+            &self.material_pool,
+            self.set_layouts[MATERIAL_SET as usize].layout,
+            &asset.path
+        )?;
+
+        Ok(Slot::Loaded(material))
+    }
+
+    /// Load a mesh while running, entities draw it through the returned `MeshRef`.
+    /// A mesh of the name of an unloaded one takes its place, and stays unloaded when its file
+    /// fails to load.
+    pub fn add_mesh(&mut self, asset: MeshAsset) -> io::Result<MeshRef> {
+        let Some(index) = self.assets.mesh_index(&asset.name) else {
+            let mesh = self.load_mesh(&asset)?;
+
+            self.meshes.push(mesh);
+            self.assets.meshes.push(asset);
+            return Ok(MeshRef(self.meshes.len() - 1));
+        };
+
+        if self.meshes[index].loaded().is_some() {
+            return Err(io::Error::new(io::ErrorKind::AlreadyExists, format!("mesh {} is already loaded", asset.name)));
+        }
+
+        let mesh = self.load_mesh(&asset);
+        self.assets.meshes[index] = asset;
+
+        match mesh {
+            Ok(mesh) => {
+                self.meshes[index] = mesh;
+                Ok(MeshRef(index))
+            }
+            Err(error) => {
+                self.meshes[index] = Slot::Failed;
+                Err(error)
+            }
+        }
+    }
+
+    /// Load a texture while running, entities draw with it through the returned `MaterialRef`.
+    /// A texture of the name of an unloaded one takes its place, and stays unloaded when its file
+    /// fails to load.
+    pub fn add_texture(&mut self, asset: TextureAsset) -> io::Result<MaterialRef> {
+        let Some(index) = self.assets.texture_index(&asset.name) else {
+            let material = self.load_material(&asset)?;
+
+            self.materials.push(material);
+            self.assets.textures.push(asset);
+            return Ok(MaterialRef(self.materials.len() - 1));
+        };
+
+        if self.materials[index].loaded().is_some() {
+            return Err(io::Error::new(io::ErrorKind::AlreadyExists, format!("texture {} is already loaded", asset.name)));
+        }
+
+        let material = self.load_material(&asset);
+        self.assets.textures[index] = asset;
+
+        match material {
+            Ok(material) => {
+                self.materials[index] = material;
+                Ok(MaterialRef(index))
+            }
+            Err(error) => {
+                self.materials[index] = Slot::Failed;
+                Err(error)
+            }
+        }
+    }
+
+    /// Unload a mesh, entities pointing at it are skipped until a mesh of its name is added.
+    /// False when it was removed already.
+    pub fn remove_mesh(&mut self, mesh: MeshRef) -> bool {
+        let Some(slot) = self.meshes.get_mut(mesh.0) else {
+            return false;
+        };
+
+        match mem::replace(slot, Slot::Removed) {
+            Slot::Loaded(removed) => {
+                // frames in flight may still draw it.
+                self.device.wait_idle();
+
+                removed.destroy();
+                true
+            }
+            Slot::Failed => true,
+            Slot::Removed => false,
+        }
+    }
+
+    /// Unload a texture, entities pointing at it are skipped until a texture of its name is added.
+    /// False when it was removed already.
+    pub fn remove_texture(&mut self, material: MaterialRef) -> bool {
+        let Some(slot) = self.materials.get_mut(material.0) else {
+            return false;
+        };
+
+        match mem::replace(slot, Slot::Removed) {
+            Slot::Loaded(removed) => {
+                // frames in flight may still draw with it.
+                self.device.wait_idle();
+
+                removed.destroy(&self.material_pool);
+                true
+            }
+            Slot::Failed => true,
+            Slot::Removed => false,
+        }
+    }
+
+    /// Load every mesh and texture that was not removed from its file again, e.g. after editing
+    /// them. Those that fail to load are left unloaded, their errors are returned.
+    pub fn reload_assets(&mut self) -> Vec<io::Error> {
+        let mut errors = Vec::new();
+
+        for index in 0..self.meshes.len() {
+            if self.remove_mesh(MeshRef(index)) {
+                if let Err(error) = self.add_mesh(self.assets.meshes[index].clone()) {
+                    errors.push(error);
+                }
+            }
+        }
+
+        for index in 0..self.materials.len() {
+            if self.remove_texture(MaterialRef(index)) {
+                if let Err(error) = self.add_texture(self.assets.textures[index].clone()) {
+                    errors.push(error);
+                }
+            }
+        }
+
+        errors
+    }
+
     pub(crate) fn resize_framebuffer(&mut self) {
         self.is_framebuffer_resized = true;
     }
@@ -723,19 +1184,21 @@ impl Renderer {
     pub fn destroy(&self) {
         self.device.wait_idle();
 
-        self.sync_objects.destroy();
+        for frame in &self.frames {
+            frame.destroy();
+        }
 
         self.cleanup_swapchain();
 
         self.descriptor_pool.destroy();
 
-        self.uniform_buffer.destroy();
-
-        self.mesh.destroy();
-        self.texture.destroy();
-
-        self.mesh2.destroy();
-        self.texture2.destroy();
+        for mesh in self.meshes.iter().filter_map(Slot::loaded) {
+            mesh.destroy();
+        }
+        for material in self.materials.iter().filter_map(Slot::loaded) {
+            material.destroy(&self.material_pool);
+        }
+        self.material_pool.destroy();
 
         for layout in &self.set_layouts {
             layout.destroy();
@@ -747,7 +1210,9 @@ impl Renderer {
 
         self.device.destroy();
         
-        self.surface.destroy();
+        if let Some(surface) = &self.surface {
+            surface.destroy();
+        }
 
         unsafe { 
             self.instance.destroy_instance(None) 
@@ -771,3 +1236,34 @@ pub(crate) fn populate_debug_messenger_create_info() -> vk::DebugUtilsMessengerC
         p_user_data: ptr::null_mut(),
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn draw(x: f32, mesh: usize, material: usize) -> Draw {
+        Draw { model: Matrix4::from_translation(Vector3::new(x, 0.0, 0.0)), mesh, material }
+    }
+
+    #[test]
+    fn draws_sharing_mesh_and_material_are_one_batch() {
+        let draws = vec![
+            vec![draw(0.0, 0, 0), draw(1.0, 1, 0), draw(2.0, 0, 0), draw(3.0, 0, 1)],
+            vec![draw(4.0, 0, 0)],
+        ];
+
+        let (batches, instances) = batch(&draws);
+
+        assert_eq!(batches, vec![
+            vec![
+                Batch { mesh: 0, material: 0, first: 0, count: 2 },
+                Batch { mesh: 1, material: 0, first: 2, count: 1 },
+                Batch { mesh: 0, material: 1, first: 3, count: 1 },
+            ],
+            vec![Batch { mesh: 0, material: 0, first: 4, count: 1 }],
+        ]);
+
+        let xs: Vec<f32> = instances.iter().map(|model| model.w.x).collect();
+        assert_eq!(xs, vec![0.0, 2.0, 1.0, 3.0, 4.0]);
+    }
+}
@@ -0,0 +1,156 @@
+use std::{ptr, rc::Rc};
+
+use ash::vk;
+
+use crate::{core::device::GraphicDevice, image::Image};
+
+use super::{buffer::Buffer, commandpool::CommandPool};
+
+pub const OFFSCREEN_FORMAT: vk::Format = vk::Format::R8G8B8A8_SRGB;
+
+/// Single sampled color target that takes the place of the swapchain images
+/// when rendering without a window.
+pub struct OffscreenImage {
+    device: Rc<GraphicDevice>,
+
+    pub(crate) image: vk::Image,
+    pub(crate) image_view: vk::ImageView,
+    pub(crate) memory: vk::DeviceMemory,
+
+    pub(crate) format: vk::Format,
+    pub(crate) extent: vk::Extent2D,
+    pub(crate) framebuffers: Vec<vk::Framebuffer>,
+}
+
+impl OffscreenImage {
+    pub fn new(device: Rc<GraphicDevice>, extent: vk::Extent2D) -> Self {
+        let (image, memory) = Image::create_image(
+            &device.logical,
+            extent.width,
+            extent.height,
+            1,
+            vk::SampleCountFlags::TYPE_1,
+            OFFSCREEN_FORMAT,
+            vk::ImageTiling::OPTIMAL,
+            vk::ImageUsageFlags::COLOR_ATTACHMENT | vk::ImageUsageFlags::TRANSFER_SRC,
+            vk::MemoryPropertyFlags::DEVICE_LOCAL,
+            &device.memory_properties,
+        );
+
+        let image_view = Image::create_image_view(
+            &device.logical,
+            image,
+            OFFSCREEN_FORMAT,
+            vk::ImageAspectFlags::COLOR,
+            1,
+        );
+
+        Self {
+            device,
+
+            image,
+            image_view,
+            memory,
+
+            format: OFFSCREEN_FORMAT,
+            extent,
+            framebuffers: Vec::new(),
+        }
+    }
+
+    pub(crate) fn create_framebuffer(
+        &mut self,
+        render_pass: &vk::RenderPass,
+        depth_image_view: vk::ImageView,
+        color_image_view: vk::ImageView,
+    ) {
+        let attachments = [color_image_view, depth_image_view, self.image_view];
+
+        let framebuffer_create_info = vk::FramebufferCreateInfo {
+            s_type: vk::StructureType::FRAMEBUFFER_CREATE_INFO,
+            p_next: ptr::null(),
+            flags: vk::FramebufferCreateFlags::empty(),
+            render_pass: *render_pass,
+            attachment_count: attachments.len() as u32,
+            p_attachments: attachments.as_ptr(),
+            width: self.extent.width,
+            height: self.extent.height,
+            layers: 1,
+        };
+
+        let framebuffer = unsafe {
+            self.device.logical
+                .create_framebuffer(&framebuffer_create_info, None)
+                .expect("Failed to create Framebuffer!")
+        };
+
+        self.framebuffers = vec![framebuffer];
+    }
+
+    /// Copy the resolved image into host memory as tightly packed RGBA8 rows.
+    /// The image must be in `TRANSFER_SRC_OPTIMAL`, as left by the offscreen render pass.
+    pub(crate) fn read(&self, command_pool: &CommandPool) -> Vec<u8> {
+        let pixel_count = (self.extent.width * self.extent.height) as usize;
+        let size = (pixel_count * 4) as u64;
+
+        let readback_buffer = Buffer::readback(self.device.clone(), size);
+
+        let command_buffer = command_pool.begin_single_time_command();
+
+        let regions = [vk::BufferImageCopy {
+            buffer_offset: 0,
+            buffer_row_length: 0,
+            buffer_image_height: 0,
+            image_subresource: vk::ImageSubresourceLayers {
+                aspect_mask: vk::ImageAspectFlags::COLOR,
+                mip_level: 0,
+                base_array_layer: 0,
+                layer_count: 1,
+            },
+            image_offset: vk::Offset3D { x: 0, y: 0, z: 0 },
+            image_extent: vk::Extent3D {
+                width: self.extent.width,
+                height: self.extent.height,
+                depth: 1,
+            },
+        }];
+
+        unsafe {
+            self.device.logical.cmd_copy_image_to_buffer(
+                command_buffer,
+                self.image,
+                vk::ImageLayout::TRANSFER_SRC_OPTIMAL,
+                readback_buffer.buffer,
+                &regions,
+            );
+        }
+
+        command_pool.end_single_time_command(command_buffer);
+
+        let pixels = readback_buffer.read::<u8>(size as usize);
+
+        readback_buffer.destroy();
+
+        pixels
+    }
+
+    pub(crate) fn destroy_framebuffers(&self) {
+        unsafe {
+            for &framebuffer in self.framebuffers.iter() {
+                self.device.logical
+                    .destroy_framebuffer(framebuffer, None);
+            }
+        }
+    }
+
+    pub(crate) fn destroy(&self) {
+        unsafe {
+            self.device.logical
+                .destroy_image_view(self.image_view, None);
+            self.device.logical
+                .destroy_image(self.image, None);
+            self.device.logical
+                .free_memory(self.memory, None);
+        }
+    }
+}
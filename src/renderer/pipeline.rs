@@ -2,11 +2,9 @@ use std::{ffi::CString, path::Path, ptr, rc::Rc};
 
 use ash::vk;
 
-use super::{
-    shader::Shader, swapchain::SwapChain
-};
+use super::shader::Shader;
 
-use crate::{core::device::GraphicDevice, mesh::Vertex};
+use crate::{core::{camera::DepthConvention, device::GraphicDevice}, mesh::Vertex};
 
 pub struct GraphicPipeline {
     device: Rc<GraphicDevice>,
@@ -19,10 +17,9 @@ impl GraphicPipeline {
     pub fn new(
         device: Rc<GraphicDevice>,
         render_pass: &vk::RenderPass,
-        swapchain: &SwapChain,
         set_layouts: &Vec<vk::DescriptorSetLayout>,
-        push_constant_size: u32,
         msaa_samples: vk::SampleCountFlags,
+        depth_convention: DepthConvention,
     ) -> Self {
         let vert_shader = Shader::from_spv(Path::new("shaders/default.vert.spv"), &device);
         let frag_shader = Shader::from_spv(Path::new("shaders/default.frag.spv"), &device);
@@ -55,12 +52,6 @@ impl GraphicPipeline {
         let binding_description = Vertex::get_binding_descriptions();
         let attribute_description = Vertex::get_attribute_descriptions();
         
-        let vertex_push_constant_range = vk::PushConstantRange {
-            stage_flags: vk::ShaderStageFlags::VERTEX,
-            offset: 0,
-            size: push_constant_size,
-        };
-            
         let vertex_input_state_create_info = vk::PipelineVertexInputStateCreateInfo {
             s_type: vk::StructureType::PIPELINE_VERTEX_INPUT_STATE_CREATE_INFO,
             p_next: ptr::null(),
@@ -78,28 +69,24 @@ impl GraphicPipeline {
             topology: vk::PrimitiveTopology::TRIANGLE_LIST,
         };
 
-        let viewports = [vk::Viewport {
-            x: 0.0,
-            y: 0.0,
-            width: swapchain.extent.width as f32,
-            height: swapchain.extent.height as f32,
-            min_depth: 0.0,
-            max_depth: 1.0,
-        }];
-
-        let scissors = [vk::Rect2D {
-            offset: vk::Offset2D { x: 0, y: 0 },
-            extent: swapchain.extent,
-        }];
-
+        // viewport and scissor are set per camera while recording, see `set_viewport`.
         let viewport_state_create_info = vk::PipelineViewportStateCreateInfo {
             s_type: vk::StructureType::PIPELINE_VIEWPORT_STATE_CREATE_INFO,
             p_next: ptr::null(),
             flags: vk::PipelineViewportStateCreateFlags::empty(),
-            scissor_count: scissors.len() as u32,
-            p_scissors: scissors.as_ptr(),
-            viewport_count: viewports.len() as u32,
-            p_viewports: viewports.as_ptr(),
+            scissor_count: 1,
+            p_scissors: ptr::null(),
+            viewport_count: 1,
+            p_viewports: ptr::null(),
+        };
+
+        let dynamic_states = [vk::DynamicState::VIEWPORT, vk::DynamicState::SCISSOR];
+        let dynamic_state_create_info = vk::PipelineDynamicStateCreateInfo {
+            s_type: vk::StructureType::PIPELINE_DYNAMIC_STATE_CREATE_INFO,
+            p_next: ptr::null(),
+            flags: vk::PipelineDynamicStateCreateFlags::empty(),
+            dynamic_state_count: dynamic_states.len() as u32,
+            p_dynamic_states: dynamic_states.as_ptr(),
         };
 
         let rasterization_statue_create_info = vk::PipelineRasterizationStateCreateInfo {
@@ -146,7 +133,7 @@ impl GraphicPipeline {
             flags: vk::PipelineDepthStencilStateCreateFlags::empty(),
             depth_test_enable: vk::TRUE,
             depth_write_enable: vk::TRUE,
-            depth_compare_op: vk::CompareOp::LESS,
+            depth_compare_op: depth_convention.compare_op(),
             depth_bounds_test_enable: vk::FALSE,
             stencil_test_enable: vk::FALSE,
             front: stencil_state,
@@ -183,8 +170,8 @@ impl GraphicPipeline {
             flags: vk::PipelineLayoutCreateFlags::empty(),
             set_layout_count: set_layouts.len() as u32,
             p_set_layouts: set_layouts.as_ptr(),
-            push_constant_range_count: 1,
-            p_push_constant_ranges: [vertex_push_constant_range].as_ptr(),
+            push_constant_range_count: 0,
+            p_push_constant_ranges: ptr::null(),
         };
 
         let pipeline_layout = unsafe {
@@ -207,7 +194,7 @@ impl GraphicPipeline {
             p_multisample_state: &multisample_state_create_info,
             p_depth_stencil_state: &depth_state_create_info,
             p_color_blend_state: &color_blend_state,
-            p_dynamic_state: ptr::null(),
+            p_dynamic_state: &dynamic_state_create_info,
             layout: pipeline_layout,
             render_pass: *render_pass,
             subpass: 0,
@@ -249,6 +236,23 @@ impl GraphicPipeline {
         }
     }
 
+    /// Restrict drawing to a rectangle of the framebuffer, in pixels.
+    pub(crate) fn set_viewport(&self, command_buffer: vk::CommandBuffer, area: vk::Rect2D) {
+        let viewports = [vk::Viewport {
+            x: area.offset.x as f32,
+            y: area.offset.y as f32,
+            width: area.extent.width as f32,
+            height: area.extent.height as f32,
+            min_depth: 0.0,
+            max_depth: 1.0,
+        }];
+
+        unsafe {
+            self.device.logical.cmd_set_viewport(command_buffer, 0, &viewports);
+            self.device.logical.cmd_set_scissor(command_buffer, 0, &[area]);
+        }
+    }
+
     pub(crate) fn destroy(&self) {
         unsafe {
             self.device.logical
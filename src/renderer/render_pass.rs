@@ -2,7 +2,7 @@ use std::{ptr, rc::Rc};
 
 use ash::vk;
 
-use crate::core::device::GraphicDevice;
+use crate::core::{camera::DepthConvention, device::GraphicDevice};
 
 use super::depth_image::DepthImage;
 
@@ -18,6 +18,7 @@ impl RenderPass {
         device: Rc<GraphicDevice>,
         format: &vk::Format,
         msaa_samples: vk::SampleCountFlags,
+        final_layout: vk::ImageLayout,
     ) -> Self {
         let color_attachment = vk::AttachmentDescription {
             flags: vk::AttachmentDescriptionFlags::empty(),
@@ -52,7 +53,7 @@ impl RenderPass {
             stencil_load_op: vk::AttachmentLoadOp::DONT_CARE,
             stencil_store_op: vk::AttachmentStoreOp::DONT_CARE,
             initial_layout: vk::ImageLayout::UNDEFINED,
-            final_layout: vk::ImageLayout::PRESENT_SRC_KHR,
+            final_layout,
         };
     
         let color_attachment_ref = vk::AttachmentReference {
@@ -86,7 +87,7 @@ impl RenderPass {
         let render_pass_attachments =
             [color_attachment, depth_attachment, color_attachment_resolve];
     
-        let subpass_dependencies = [vk::SubpassDependency {
+        let mut subpass_dependencies = vec![vk::SubpassDependency {
             src_subpass: vk::SUBPASS_EXTERNAL,
             dst_subpass: 0,
             src_stage_mask: vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT,
@@ -96,6 +97,19 @@ impl RenderPass {
                 | vk::AccessFlags::COLOR_ATTACHMENT_WRITE,
             dependency_flags: vk::DependencyFlags::empty(),
         }];
+
+        // the resolved image is copied back to the host once the pass is over.
+        if final_layout == vk::ImageLayout::TRANSFER_SRC_OPTIMAL {
+            subpass_dependencies.push(vk::SubpassDependency {
+                src_subpass: 0,
+                dst_subpass: vk::SUBPASS_EXTERNAL,
+                src_stage_mask: vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT,
+                dst_stage_mask: vk::PipelineStageFlags::TRANSFER,
+                src_access_mask: vk::AccessFlags::COLOR_ATTACHMENT_WRITE,
+                dst_access_mask: vk::AccessFlags::TRANSFER_READ,
+                dependency_flags: vk::DependencyFlags::empty(),
+            });
+        }
     
         let renderpass_create_info = vk::RenderPassCreateInfo {
             s_type: vk::StructureType::RENDER_PASS_CREATE_INFO,
@@ -126,7 +140,8 @@ impl RenderPass {
         &self, 
         command_buffer: vk::CommandBuffer, 
         extent: vk::Extent2D,
-        framebuffer: vk::Framebuffer
+        framebuffer: vk::Framebuffer,
+        depth_convention: DepthConvention
     ) {
         let clear_values = [
             vk::ClearValue {
@@ -138,7 +153,7 @@ impl RenderPass {
             vk::ClearValue {
                 // clear value for depth buffer
                 depth_stencil: vk::ClearDepthStencilValue {
-                    depth: 1.0,
+                    depth: depth_convention.clear_depth(),
                     stencil: 0,
                 },
             },
@@ -166,6 +181,46 @@ impl RenderPass {
         }
     }
 
+    /// Clear color and depth inside `area` in the middle of the pass, like `begin` does for the whole framebuffer.
+    pub(crate) fn clear(
+        &self, 
+        command_buffer: vk::CommandBuffer, 
+        area: vk::Rect2D,
+        depth_convention: DepthConvention
+    ) {
+        let attachments = [
+            vk::ClearAttachment {
+                aspect_mask: vk::ImageAspectFlags::COLOR,
+                color_attachment: 0,
+                clear_value: vk::ClearValue {
+                    color: vk::ClearColorValue {
+                        float32: [0.0, 0.0, 0.0, 1.0],
+                    },
+                },
+            },
+            vk::ClearAttachment {
+                aspect_mask: vk::ImageAspectFlags::DEPTH,
+                color_attachment: 0,
+                clear_value: vk::ClearValue {
+                    depth_stencil: vk::ClearDepthStencilValue {
+                        depth: depth_convention.clear_depth(),
+                        stencil: 0,
+                    },
+                },
+            },
+        ];
+
+        let rects = [vk::ClearRect {
+            rect: area,
+            base_array_layer: 0,
+            layer_count: 1,
+        }];
+
+        unsafe {
+            self.device.logical.cmd_clear_attachments(command_buffer, &attachments, &rects);
+        }
+    }
+
     pub(crate) fn end(&self, command_buffer: vk::CommandBuffer) {
         unsafe {
             self.device.logical.cmd_end_render_pass(command_buffer);
@@ -0,0 +1,75 @@
+use ash::vk;
+
+use super::{offscreen_image::OffscreenImage, swapchain::SwapChain};
+
+/// Images the render pass resolves into, either the window swapchain or an offscreen image.
+pub enum RenderTarget {
+    Swapchain(SwapChain),
+    Offscreen(OffscreenImage),
+}
+
+impl RenderTarget {
+    pub(crate) fn format(&self) -> vk::Format {
+        match self {
+            Self::Swapchain(swapchain) => swapchain.format,
+            Self::Offscreen(offscreen) => offscreen.format,
+        }
+    }
+
+    pub(crate) fn extent(&self) -> vk::Extent2D {
+        match self {
+            Self::Swapchain(swapchain) => swapchain.extent,
+            Self::Offscreen(offscreen) => offscreen.extent,
+        }
+    }
+
+    /// Layout the resolved image is left in at the end of the render pass.
+    pub(crate) fn final_layout(&self) -> vk::ImageLayout {
+        match self {
+            Self::Swapchain(_) => vk::ImageLayout::PRESENT_SRC_KHR,
+            Self::Offscreen(_) => vk::ImageLayout::TRANSFER_SRC_OPTIMAL,
+        }
+    }
+
+    pub(crate) fn framebuffers(&self) -> &Vec<vk::Framebuffer> {
+        match self {
+            Self::Swapchain(swapchain) => &swapchain.framebuffers,
+            Self::Offscreen(offscreen) => &offscreen.framebuffers,
+        }
+    }
+
+    pub(crate) fn swapchain(&self) -> &SwapChain {
+        match self {
+            Self::Swapchain(swapchain) => swapchain,
+            Self::Offscreen(_) => panic!("Offscreen render target has no swapchain!"),
+        }
+    }
+
+    pub(crate) fn create_framebuffer(
+        &mut self,
+        render_pass: &vk::RenderPass,
+        depth_image_view: vk::ImageView,
+        color_image_view: vk::ImageView,
+    ) {
+        match self {
+            Self::Swapchain(swapchain) => 
+                swapchain.create_framebuffer(render_pass, depth_image_view, color_image_view),
+            Self::Offscreen(offscreen) => 
+                offscreen.create_framebuffer(render_pass, depth_image_view, color_image_view),
+        }
+    }
+
+    pub(crate) fn destroy_framebuffers(&self) {
+        match self {
+            Self::Swapchain(swapchain) => swapchain.destroy_framebuffers(),
+            Self::Offscreen(offscreen) => offscreen.destroy_framebuffers(),
+        }
+    }
+
+    pub(crate) fn destroy(&self) {
+        match self {
+            Self::Swapchain(swapchain) => swapchain.destroy(),
+            Self::Offscreen(offscreen) => offscreen.destroy(),
+        }
+    }
+}